@@ -1,9 +1,19 @@
 use cosmwasm_std::{
-  entry_point, to_binary, Deps, DepsMut, Env, MessageInfo, QueryResponse, Response, StdResult,
+  entry_point, to_binary, Binary, Deps, DepsMut, Env, MessageInfo, QueryResponse, Response,
 };
 
 use crate::contract_config::ContractConfig;
-use crate::{msg, state};
+use crate::error::ContractError;
+use crate::{defs, msg, state};
+use secret_toolkit::utils::padding::space_pad;
+
+/// pads with spaces to a multiple of the block size. 0 disables padding.
+fn pad(mut data: Binary, block_size: u32) -> Binary {
+  if block_size > 0 {
+    space_pad(&mut data.0, block_size as usize);
+  }
+  data
+}
 
 #[entry_point]
 pub fn instantiate(
@@ -11,12 +21,15 @@ pub fn instantiate(
   env: Env,
   info: MessageInfo,
   msg: msg::InstantiateMsg,
-) -> StdResult<Response> {
+) -> Result<Response, ContractError> {
   let config = ContractConfig {
     my_address: env.contract.address,
     owner_address: info.sender,
     storage_address: deps.api.addr_validate(msg.storage_address.as_str())?,
     storage_codehash: msg.storage_codehash,
+    padding_block_size: msg
+      .padding_block_size
+      .unwrap_or(defs::PADDING_BLOCK_SIZE_DEFAULT),
   };
   config.save(deps.storage)?;
 
@@ -29,23 +42,128 @@ pub fn execute(
   env: Env,
   info: MessageInfo,
   msg: msg::ExecuteMsg,
-) -> StdResult<Response> {
+) -> Result<Response, ContractError> {
   let config = ContractConfig::load(deps.storage)?;
-  match msg {
+  let block_size = config.padding_block_size;
+  let r = match msg {
     msg::ExecuteMsg::Set {
       permit,
+      viewing_key,
       key,
       value,
       authz,
-    } => state::set(deps, env, info, config, permit, key, value, authz),
-  }
+    } => state::set(
+      deps,
+      env,
+      info,
+      config,
+      permit,
+      viewing_key,
+      key,
+      value,
+      authz,
+    ),
+    msg::ExecuteMsg::RevokePermit {
+      permit,
+      viewing_key,
+      permit_name,
+    } => state::revoke_permit(deps, env, info, config, permit, viewing_key, permit_name),
+  };
+  let mut res = r?;
+  res.data = res.data.map(|d| pad(d, block_size));
+  Ok(res)
 }
 
 #[entry_point]
-pub fn query(deps: Deps, env: Env, msg: msg::QueryMsg) -> StdResult<QueryResponse> {
+pub fn query(deps: Deps, env: Env, msg: msg::QueryMsg) -> Result<QueryResponse, ContractError> {
   let config = ContractConfig::load(deps.storage)?;
-  let r: StdResult<msg::QueryAnswer> = match msg {
-    msg::QueryMsg::Get { permit, key } => state::get(deps, env, config, permit, key),
+  let block_size = config.padding_block_size;
+  let r: Result<msg::QueryAnswer, ContractError> = match msg {
+    msg::QueryMsg::Get {
+      permit,
+      viewing_key,
+      owner,
+      key,
+    } => state::get(deps, env, config, permit, viewing_key, owner, key),
+    msg::QueryMsg::GetMany {
+      permit,
+      viewing_key,
+      owner,
+      keys,
+    } => state::get_many(deps, env, config, permit, viewing_key, owner, keys),
+    msg::QueryMsg::RevokedPermits {
+      permit,
+      viewing_key,
+      start_after,
+      limit,
+    } => state::revoked_permits(deps, env, config, permit, viewing_key, start_after, limit),
+    msg::QueryMsg::Ping {} => Ok(msg::QueryAnswer::Pong {}),
   };
-  r.and_then(|a| to_binary(&a))
+  Ok(pad(to_binary(&r?)?, block_size))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+  use cosmwasm_std::{ContractResult, SystemResult};
+
+  /// queries Get, the storage contract answering `answer`
+  fn get(answer: ContractResult<Binary>) -> Result<Binary, ContractError> {
+    let mut deps = mock_dependencies();
+    let msg = msg::InstantiateMsg {
+      storage_address: "storage".to_string(),
+      storage_codehash: "hash".to_string(),
+      padding_block_size: None,
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info("admin", &[]), msg).unwrap();
+    deps
+      .querier
+      .update_wasm(move |_| SystemResult::Ok(answer.clone()));
+    let msg = msg::QueryMsg::Get {
+      permit: None,
+      viewing_key: None,
+      owner: None,
+      key: "key".to_string(),
+    };
+    query(deps.as_ref(), mock_env(), msg)
+  }
+
+  fn query_len(data: Option<Vec<u8>>) -> usize {
+    let answer = crate::storage::QueryAnswer::Data(data.map(|data| crate::storage::DataOutput {
+      key: "key".to_string(),
+      version: "1".to_string(),
+      data,
+      authz: crate::storage::Authz::new("alice", false),
+      revision: 1,
+      expires_at: None,
+      pending_owner: None,
+      created_at: None,
+      updated_at: None,
+      updated_by: None,
+    }));
+    get(ContractResult::Ok(to_binary(&answer).unwrap()))
+      .unwrap()
+      .len()
+  }
+
+  #[test]
+  fn query_answers_are_padded_uniformly() {
+    assert_eq!(query_len(Some(vec![0, 0, 0, 1])), 256);
+    assert_eq!(query_len(Some(vec![255, 255, 255, 255])), 256);
+    assert_eq!(query_len(None), 256);
+  }
+
+  #[test]
+  fn storage_error_codes_survive_a_failed_query() {
+    let err = ContractError::Unauthorized("not a reader".into());
+    assert_eq!(get(ContractResult::Err(err.to_string())).unwrap_err(), err);
+    let err = ContractError::NotFound("op 1: entry".into());
+    assert_eq!(get(ContractResult::Err(err.to_string())).unwrap_err(), err);
+    assert!(matches!(
+      get(ContractResult::Err("Generic error: bad".into())).unwrap_err(),
+      ContractError::Std(_)
+    ));
+  }
 }
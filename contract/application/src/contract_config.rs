@@ -1,5 +1,6 @@
 use crate::defs;
-use cosmwasm_std::{Addr, StdError, StdResult, Storage};
+use crate::error::ContractError;
+use cosmwasm_std::{Addr, StdResult, Storage};
 use cosmwasm_storage::{singleton, singleton_read};
 use serde::{Deserialize, Serialize};
 
@@ -9,12 +10,19 @@ pub struct ContractConfig {
   pub owner_address: Addr,
   pub storage_address: Addr,
   pub storage_codehash: String,
+  /// query answers and response data are padded to a multiple of it. 0 disables padding.
+  #[serde(default = "default_padding_block_size")]
+  pub padding_block_size: u32,
+}
+
+fn default_padding_block_size() -> u32 {
+  defs::PADDING_BLOCK_SIZE_DEFAULT
 }
 
 impl ContractConfig {
-  pub fn check_owner(&self, addr: &Addr) -> StdResult<()> {
+  pub fn check_owner(&self, addr: &Addr) -> Result<(), ContractError> {
     if &self.owner_address != addr {
-      return Err(StdError::generic_err(format!("not a owner")));
+      return Err(ContractError::Unauthorized("not a owner".into()));
     }
     Ok(())
   }
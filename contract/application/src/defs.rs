@@ -1,2 +1,4 @@
 pub const CONTRACT_CONFIG_KEY: &str = "contract_config";
 pub const CONTRACT_CONFIG_KEY_B: &[u8] = b"contract_config";
+
+pub const PADDING_BLOCK_SIZE_DEFAULT: u32 = 256;
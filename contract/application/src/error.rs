@@ -0,0 +1,2 @@
+// the error type is shared with the storage contract, see the Makefile.
+pub use crate::storage_error::ContractError;
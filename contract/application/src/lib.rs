@@ -1,6 +1,8 @@
 pub mod contract;
 pub mod contract_config;
 pub mod defs;
+pub mod error;
 pub mod msg;
 pub mod state;
 pub mod storage;
+pub mod storage_error;
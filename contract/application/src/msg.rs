@@ -7,6 +7,8 @@ use serde::{Deserialize, Serialize}; //use upgradable_contract__storage::msg as
 pub struct InstantiateMsg {
   pub storage_address: String,
   pub storage_codehash: String,
+  /// defaults to 256.
+  pub padding_block_size: Option<u32>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -18,14 +20,41 @@ pub struct QueryGet {}
 pub enum QueryMsg {
   Get {
     permit: Option<storage::Permit>,
+    viewing_key: Option<storage::ViewingKey>,
+    owner: Option<String>,
     key: String,
   },
+  GetMany {
+    permit: Option<storage::Permit>,
+    viewing_key: Option<storage::ViewingKey>,
+    owner: Option<String>,
+    keys: Vec<String>,
+  },
+  RevokedPermits {
+    permit: Option<storage::Permit>,
+    viewing_key: Option<storage::ViewingKey>,
+    start_after: Option<String>,
+    limit: Option<u32>,
+  },
+  /// answered so that the storage contract can verify our code hash.
+  Ping {},
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ValueResult {
+  Found { key: String, value: u32 },
+  Missing { key: String },
+  Forbidden { key: String },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryAnswer {
   Value(Option<u32>),
+  Values(Vec<ValueResult>),
+  RevokedPermits(Vec<String>),
+  Pong {},
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -41,8 +70,14 @@ pub enum Authz {
 pub enum ExecuteMsg {
   Set {
     permit: Option<storage::Permit>,
+    viewing_key: Option<storage::ViewingKey>,
     key: String,
     value: u32,
     authz: Authz,
   },
+  RevokePermit {
+    permit: Option<storage::Permit>,
+    viewing_key: Option<storage::ViewingKey>,
+    permit_name: String,
+  },
 }
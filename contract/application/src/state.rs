@@ -1,9 +1,10 @@
-use cosmwasm_std::{Deps, DepsMut, Env, MessageInfo, Response, StdError, StdResult};
+use cosmwasm_std::{Deps, DepsMut, Env, MessageInfo, Response};
 
 use crate::storage; //use upgradable_contract__storage::msg as storage;
 use secret_toolkit::utils::calls::{HandleCallback, Query};
 
 use crate::contract_config::ContractConfig;
+use crate::error::ContractError;
 use crate::msg;
 
 struct Data {
@@ -12,7 +13,7 @@ struct Data {
 fn format_data(data: &Data) -> (String, Vec<u8>) {
   ("1".to_string(), Vec::from(data.value.to_be_bytes()))
 }
-fn parse_data(out: &storage::DataOutput) -> StdResult<Data> {
+fn parse_data(out: &storage::DataOutput) -> Result<Data, ContractError> {
   match out.version.as_str() {
     "1" => {
       let bytes: [u8; 4] = [out.data[0], out.data[1], out.data[2], out.data[3]];
@@ -20,7 +21,7 @@ fn parse_data(out: &storage::DataOutput) -> StdResult<Data> {
         value: u32::from_be_bytes(bytes),
       })
     }
-    _ => Err(StdError::generic_err("unknown format version")),
+    _ => Err(ContractError::UnknownFormatVersion(out.version.clone())),
   }
 }
 
@@ -30,10 +31,11 @@ pub fn set(
   _info: MessageInfo,
   config: ContractConfig,
   permit: Option<storage::Permit>,
+  viewing_key: Option<storage::ViewingKey>,
   key: String,
   value: u32,
   authz: msg::Authz,
-) -> StdResult<Response> {
+) -> Result<Response, ContractError> {
   let f = format_data(&Data { value: value });
   let storage_authz = match authz {
     //owner is automatically set in storage contract
@@ -43,10 +45,12 @@ pub fn set(
   };
   let msg = storage::ExecuteMsg::Store(storage::ExecuteStore {
     permit: permit,
+    viewing_key: viewing_key,
     key: key,
     version: f.0,
     data: f.1,
     authz: storage_authz,
+    expires_at: None,
   });
   let res = Response::new().add_message(msg.to_cosmos_msg(
     config.storage_codehash,
@@ -61,10 +65,14 @@ pub fn get(
   _env: Env,
   config: ContractConfig,
   permit: Option<storage::Permit>,
+  viewing_key: Option<storage::ViewingKey>,
+  owner: Option<String>,
   key: String,
-) -> StdResult<msg::QueryAnswer> {
+) -> Result<msg::QueryAnswer, ContractError> {
   let msg = storage::QueryMsg::Get(storage::QueryGet {
     permit: permit,
+    viewing_key: viewing_key,
+    owner: owner,
     key: key,
   });
   match msg.query(
@@ -77,10 +85,91 @@ pub fn get(
       Ok(msg::QueryAnswer::Value(Some(data.value)))
     }
     storage::QueryAnswer::Data(None) => Ok(msg::QueryAnswer::Value(None)),
-    /*
-    _ => {
-      Err(StdError::generic_err("unexpected response")),
+    _ => Err(ContractError::UnexpectedResponse("storage answer".into())),
+  }
+}
+
+pub fn get_many(
+  deps: Deps,
+  _env: Env,
+  config: ContractConfig,
+  permit: Option<storage::Permit>,
+  viewing_key: Option<storage::ViewingKey>,
+  owner: Option<String>,
+  keys: Vec<String>,
+) -> Result<msg::QueryAnswer, ContractError> {
+  let msg = storage::QueryMsg::GetMany(storage::QueryGetMany {
+    permit: permit,
+    viewing_key: viewing_key,
+    owner: owner,
+    keys: keys,
+  });
+  match msg.query(
+    deps.querier,
+    config.storage_codehash,
+    config.storage_address.to_string(),
+  )? {
+    storage::QueryAnswer::Many(results) => {
+      let mut values = Vec::new();
+      for r in results {
+        values.push(match r {
+          storage::GetManyResult::Found(o) => msg::ValueResult::Found {
+            value: parse_data(&o)?.value,
+            key: o.key,
+          },
+          storage::GetManyResult::Missing { key } => msg::ValueResult::Missing { key: key },
+          storage::GetManyResult::Forbidden { key } => msg::ValueResult::Forbidden { key: key },
+        });
+      }
+      Ok(msg::QueryAnswer::Values(values))
     }
-     */
+    _ => Err(ContractError::UnexpectedResponse("storage answer".into())),
+  }
+}
+
+pub fn revoke_permit(
+  _deps: DepsMut,
+  _env: Env,
+  _info: MessageInfo,
+  config: ContractConfig,
+  permit: Option<storage::Permit>,
+  viewing_key: Option<storage::ViewingKey>,
+  permit_name: String,
+) -> Result<Response, ContractError> {
+  let msg = storage::ExecuteMsg::RevokePermit(storage::ExecuteRevokePermit {
+    permit: permit,
+    viewing_key: viewing_key,
+    permit_name: permit_name,
+  });
+  let res = Response::new().add_message(msg.to_cosmos_msg(
+    config.storage_codehash,
+    config.storage_address.to_string(),
+    None,
+  )?);
+  Ok(res)
+}
+
+pub fn revoked_permits(
+  deps: Deps,
+  _env: Env,
+  config: ContractConfig,
+  permit: Option<storage::Permit>,
+  viewing_key: Option<storage::ViewingKey>,
+  start_after: Option<String>,
+  limit: Option<u32>,
+) -> Result<msg::QueryAnswer, ContractError> {
+  let msg = storage::QueryMsg::RevokedPermits(storage::QueryRevokedPermits {
+    permit: permit,
+    viewing_key: viewing_key,
+    start_after: start_after,
+    limit: limit,
+  });
+  match msg.query(
+    deps.querier,
+    config.storage_codehash,
+    config.storage_address.to_string(),
+  )? {
+    storage::QueryAnswer::RevokedPermits(names) => Ok(msg::QueryAnswer::RevokedPermits(names)),
+    _ => Err(ContractError::UnexpectedResponse("storage answer".into())),
   }
 }
@@ -15,4 +15,5 @@ fn main() {
   export_schema(&schema_for!(msg::ExecuteMsg), &out_dir);
   export_schema(&schema_for!(msg::QueryMsg), &out_dir);
   export_schema(&schema_for!(msg::QueryAnswer), &out_dir);
+  export_schema(&schema_for!(msg::ExecuteAnswer), &out_dir);
 }
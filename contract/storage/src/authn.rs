@@ -0,0 +1,156 @@
+use crate::contract_config::ContractConfig;
+use crate::error::ContractError;
+use crate::{defs, index, msg};
+use cosmwasm_std::{Addr, BlockInfo, Deps, DepsMut, Env, MessageInfo, Response, StdResult};
+use secret_toolkit::permit::RevokedPermits;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Scope {
+  /// queries
+  Read,
+  /// storing and updating data
+  Write,
+  /// changing authz, ownership, groups and deleting
+  Admin,
+}
+
+/// an authenticated account and what it is allowed to do
+#[derive(Clone, Debug, PartialEq)]
+pub struct Authn {
+  pub account: Addr,
+  scopes: Vec<Scope>,
+  /// empty allows any key
+  key_prefixes: Vec<String>,
+}
+
+impl Authn {
+  pub fn full(account: Addr) -> Self {
+    Self {
+      account,
+      scopes: vec![Scope::Read, Scope::Write, Scope::Admin],
+      key_prefixes: vec![],
+    }
+  }
+
+  pub fn from_permissions(
+    account: Addr,
+    block: &BlockInfo,
+    permissions: &[msg::Permissions],
+  ) -> Result<Self, ContractError> {
+    let mut scopes = vec![];
+    let mut key_prefixes = vec![];
+    for p in permissions {
+      match p {
+        msg::Permissions::Access => {
+          scopes.extend_from_slice(&[Scope::Read, Scope::Write, Scope::Admin])
+        }
+        msg::Permissions::Read => scopes.push(Scope::Read),
+        msg::Permissions::Write => scopes.push(Scope::Write),
+        msg::Permissions::Admin => scopes.push(Scope::Admin),
+        msg::Permissions::ExpiresAt(e) => {
+          if e.is_expired(block) {
+            return Err(ContractError::Unauthorized("permit expired".into()));
+          }
+        }
+        msg::Permissions::KeyPrefix(s) => key_prefixes.push(s.clone()),
+      }
+    }
+    Ok(Self {
+      account,
+      scopes,
+      key_prefixes,
+    })
+  }
+
+  /// drops the write and admin scopes.
+  pub fn read_only(mut self) -> Self {
+    self.scopes.retain(|s| *s == Scope::Read);
+    self
+  }
+
+  pub fn allows_key(&self, key: &str) -> bool {
+    self.key_prefixes.is_empty()
+      || self
+        .key_prefixes
+        .iter()
+        .any(|p| key.starts_with(p.as_str()))
+  }
+
+  pub fn check(&self, scope: Scope, key: Option<&str>) -> Result<(), ContractError> {
+    if !self.scopes.contains(&scope) {
+      return Err(ContractError::Unauthorized(format!(
+        "permit has no {:?} scope",
+        scope
+      )));
+    }
+    if let Some(key) = key {
+      if !self.allows_key(key) {
+        return Err(ContractError::Unauthorized(
+          "key not allowed by permit".into(),
+        ));
+      }
+    }
+    Ok(())
+  }
+}
+
+fn revoked_index_namespace(account: &Addr) -> [&[u8]; 2] {
+  [defs::REVOKED_PERMIT_INDEX_KEY, account.as_bytes()]
+}
+
+pub fn revoke_permit(
+  deps: DepsMut,
+  _env: Env,
+  _info: MessageInfo,
+  _config: &ContractConfig,
+  authn: Option<Authn>,
+  msg: msg::ExecuteRevokePermit,
+) -> Result<Response, ContractError> {
+  if authn.is_none() {
+    return Err(ContractError::Unauthorized(
+      "no permit or viewing key".into(),
+    ));
+  }
+  let authn = authn.unwrap();
+  authn.check(Scope::Admin, None)?;
+  RevokedPermits::revoke_permit(
+    deps.storage,
+    defs::PREFIX_REVOKED_PERMIT,
+    authn.account.as_str(),
+    msg.permit_name.as_str(),
+  );
+  index::insert(
+    deps.storage,
+    &revoked_index_namespace(&authn.account),
+    msg.permit_name.as_str(),
+  )?;
+  Ok(Response::new())
+}
+
+pub fn revoked_permits(
+  deps: Deps,
+  _env: Env,
+  _config: &ContractConfig,
+  authn: Option<Authn>,
+  msg: msg::QueryRevokedPermits,
+) -> Result<msg::QueryAnswer, ContractError> {
+  if authn.is_none() {
+    return Err(ContractError::Unauthorized(
+      "no permit or viewing key".into(),
+    ));
+  }
+  let authn = authn.unwrap();
+  authn.check(Scope::Read, None)?;
+  let limit = msg
+    .limit
+    .unwrap_or(defs::LIST_DEFAULT_LIMIT)
+    .min(defs::LIST_MAX_LIMIT) as usize;
+  let names = index::keys_after(
+    deps.storage,
+    &revoked_index_namespace(&authn.account),
+    msg.start_after.as_deref(),
+  )
+  .take(limit)
+  .collect::<StdResult<Vec<_>>>()?;
+  Ok(msg::QueryAnswer::RevokedPermits(names))
+}
@@ -0,0 +1,50 @@
+use crate::defs;
+use cosmwasm_std::{Addr, StdResult, Storage};
+use cosmwasm_storage::{singleton, singleton_read, Bucket, ReadonlyBucket};
+use serde::{Deserialize, Serialize};
+
+// every write and delete of an entry takes the next storage-wide sequence number
+// and is logged under it, so clients can sync the changes after a known one.
+// only the last CHANGES_RETENTION changes are kept. who may see a change is
+// decided by the entry as it is now, so the log holds no authz.
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Change {
+  pub owner: Addr,
+  pub key: String,
+  pub deleted: bool,
+}
+
+/// sequence number of the last change, 0 when nothing has changed yet
+pub fn latest(storage: &dyn Storage) -> StdResult<u64> {
+  Ok(
+    singleton_read::<u64>(storage, defs::CHANGE_SEQ_KEY)
+      .may_load()?
+      .unwrap_or(0),
+  )
+}
+
+pub fn record(storage: &mut dyn Storage, owner: &Addr, key: &str, deleted: bool) -> StdResult<u64> {
+  let seq = latest(storage)? + 1;
+  singleton::<u64>(storage, defs::CHANGE_SEQ_KEY).save(&seq)?;
+  let change = Change {
+    owner: owner.clone(),
+    key: key.to_string(),
+    deleted,
+  };
+  let mut bucket = Bucket::new(storage, defs::CHANGE_BUCKET_KEY);
+  bucket.save(&seq.to_be_bytes(), &change)?;
+  if seq > defs::CHANGES_RETENTION {
+    bucket.remove(&(seq - defs::CHANGES_RETENTION).to_be_bytes());
+  }
+  Ok(seq)
+}
+
+/// the sequence number of the oldest change still kept
+pub fn oldest(storage: &dyn Storage) -> StdResult<u64> {
+  Ok(latest(storage)?.saturating_sub(defs::CHANGES_RETENTION) + 1)
+}
+
+pub fn load(storage: &dyn Storage, seq: u64) -> StdResult<Option<Change>> {
+  ReadonlyBucket::new(storage, defs::CHANGE_BUCKET_KEY).may_load(&seq.to_be_bytes())
+}
@@ -0,0 +1,110 @@
+use crate::authn::{Authn, Scope};
+use crate::contract_config::{Application, ContractConfig};
+use crate::error::ContractError;
+use crate::{defs, msg};
+use cosmwasm_std::{Addr, Deps, DepsMut, Env, MessageInfo, Response, StdResult, Storage};
+use cosmwasm_storage::{bucket, bucket_read};
+use serde::{Deserialize, Serialize};
+
+// per-account allowlist of applications which may relay the account's operations.
+// a relayer is approved by its address, or by its code hash when it is registered
+// and verified to run it; the code hash of an unverified registration is only claimed.
+// an account without any approval accepts every relayer.
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
+pub struct Consent {
+  applications: Vec<Addr>,
+  code_hashes: Vec<String>,
+}
+
+impl Consent {
+  pub fn approves(&self, address: &Addr, app: Option<&Application>) -> bool {
+    (self.applications.is_empty() && self.code_hashes.is_empty())
+      || self.applications.contains(address)
+      || app
+        .iter()
+        .any(|a| a.verified && self.code_hashes.contains(&a.code_hash))
+  }
+}
+
+pub fn load(storage: &dyn Storage, account: &Addr) -> Result<Consent, ContractError> {
+  Ok(
+    bucket_read::<Consent>(storage, defs::CONSENT_BUCKET_KEY)
+      .may_load(account.as_bytes())?
+      .unwrap_or_default(),
+  )
+}
+
+/// `app` is the registration of the relayer, if any
+pub fn check(
+  storage: &dyn Storage,
+  account: &Addr,
+  relayer: &Addr,
+  app: Option<&Application>,
+) -> Result<(), ContractError> {
+  if !load(storage, account)?.approves(relayer, app) {
+    return Err(ContractError::Unauthorized(
+      "application not approved".into(),
+    ));
+  }
+  Ok(())
+}
+
+pub fn set(
+  deps: DepsMut,
+  _env: Env,
+  _info: MessageInfo,
+  _config: &ContractConfig,
+  authn: Option<Authn>,
+  msg: msg::ExecuteSetConsent,
+) -> Result<Response, ContractError> {
+  if authn.is_none() {
+    return Err(ContractError::Unauthorized(
+      "no permit or viewing key".into(),
+    ));
+  }
+  let authn = authn.unwrap();
+  authn.check(Scope::Admin, None)?;
+  let mut applications = msg
+    .applications
+    .iter()
+    .map(|s| deps.api.addr_validate(s.as_str()))
+    .collect::<StdResult<Vec<_>>>()?;
+  applications.sort();
+  applications.dedup();
+  let mut code_hashes = msg.code_hashes;
+  code_hashes.sort();
+  code_hashes.dedup();
+  let consent = Consent {
+    applications,
+    code_hashes,
+  };
+  let mut bkt = bucket::<Consent>(deps.storage, defs::CONSENT_BUCKET_KEY);
+  if consent == Consent::default() {
+    bkt.remove(authn.account.as_bytes());
+  } else {
+    bkt.save(authn.account.as_bytes(), &consent)?;
+  }
+  Ok(Response::new())
+}
+
+pub fn get(
+  deps: Deps,
+  _env: Env,
+  _config: &ContractConfig,
+  authn: Option<Authn>,
+  _msg: msg::QueryConsent,
+) -> Result<msg::QueryAnswer, ContractError> {
+  if authn.is_none() {
+    return Err(ContractError::Unauthorized(
+      "no permit or viewing key".into(),
+    ));
+  }
+  let authn = authn.unwrap();
+  authn.check(Scope::Read, None)?;
+  let consent = load(deps.storage, &authn.account)?;
+  Ok(msg::QueryAnswer::Consent(msg::ConsentOutput {
+    applications: consent.applications.iter().map(|a| a.to_string()).collect(),
+    code_hashes: consent.code_hashes,
+  }))
+}
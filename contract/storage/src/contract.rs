@@ -1,44 +1,161 @@
 use cosmwasm_std::{
-  entry_point, to_binary, Addr, Deps, DepsMut, Env, MessageInfo, QueryResponse, Response, StdError,
-  StdResult,
+  entry_point, to_binary, Addr, Binary, Deps, DepsMut, Env, MessageInfo, QueryResponse, Response,
+  StdError,
 };
 use secret_toolkit::permit::validate;
+use secret_toolkit::utils::calls::Query;
+use secret_toolkit::utils::padding::space_pad;
 //use std::cmp::max;
 
-use crate::contract_config::ContractConfig;
-use crate::{defs, msg, state};
+use crate::authn::{self, Authn};
+use crate::contract_config::{Application, ContractConfig};
+use crate::error::ContractError;
+use crate::{consent, defs, group, msg, state, viewing_key};
 
+/// `sender` is the sender of an execute, checked against the caller policy and,
+/// when it relays for another account, against the account's consent.
 fn authenticate(
   deps: Deps,
+  env: &Env,
   config: &ContractConfig,
+  sender: Option<&Addr>,
   permit: Option<&msg::Permit>,
-) -> StdResult<Option<Addr>> {
+  vk: Option<&msg::ViewingKey>,
+) -> Result<Option<Authn>, ContractError> {
+  let authn = authenticate_credentials(deps, env, config, sender, permit, vk)?;
+  if let (Some(sender), Some(authn)) = (sender, authn.as_ref()) {
+    config.check_sender(sender, &authn.account)?;
+    if sender != &authn.account {
+      consent::check(
+        deps.storage,
+        &authn.account,
+        sender,
+        config.application(sender),
+      )?;
+    }
+  }
+  Ok(authn)
+}
+
+fn authenticate_credentials(
+  deps: Deps,
+  env: &Env,
+  config: &ContractConfig,
+  sender: Option<&Addr>,
+  permit: Option<&msg::Permit>,
+  vk: Option<&msg::ViewingKey>,
+) -> Result<Option<Authn>, ContractError> {
+  if let Some(vk) = vk {
+    if permit.is_some() {
+      return Err(ContractError::InvalidRequest(
+        "both permit and viewing key".into(),
+      ));
+    }
+    let authn = Authn::full(viewing_key::authenticate(deps, vk)?);
+    return match sender {
+      // the account sends the execute itself
+      Some(sender) if sender == &authn.account => Ok(Some(authn)),
+      // a viewing key names no application, so the relaying one must be trusted
+      Some(sender) => {
+        config.check_application(sender, &env.block)?;
+        Ok(Some(authn))
+      }
+      // whoever knows the key may read only
+      None => Ok(Some(authn.read_only())),
+    };
+  }
   if permit.is_none() {
     return Ok(None);
   }
   let permit = permit.unwrap();
   if permit.params.allowed_tokens.len() == 0 {
-    return Err(StdError::generic_err("no allowed_tokens"));
+    return Err(ContractError::Unauthorized("no allowed_tokens".into()));
+  }
+  if permit.params.chain_id != env.block.chain_id {
+    return Err(ContractError::Unauthorized("wrong chain_id".into()));
   }
 
-  // check allowed_tokens is one of applications address
-  let current_token_address = config
-    .applications_address
-    .iter()
-    .find(|a| permit.check_token(a.as_str()));
-  if current_token_address.is_none() {
-    return Err(StdError::generic_err("no allowed address"));
+  // check allowed_tokens is one of enabled applications address
+  let current_app = config.applications.iter().find(|a| {
+    permit.check_token(a.address.as_str()) && a.is_enabled(&env.block) && config.is_trusted(a)
+  });
+  if current_app.is_none() {
+    return Err(ContractError::Unauthorized("no allowed address".into()));
   }
+  let current_app = current_app.unwrap();
 
   let addr_s = validate(
     deps,
     defs::PREFIX_REVOKED_PERMIT,
     &permit,
-    current_token_address.unwrap().to_string(),
+    current_app.address.to_string(),
     None,
   )?;
   let addr_h = deps.api.addr_validate(addr_s.as_str())?;
-  Ok(Some(addr_h))
+  consent::check(
+    deps.storage,
+    &addr_h,
+    &current_app.address,
+    Some(current_app),
+  )?;
+  let authn = Authn::from_permissions(addr_h, &env.block, &permit.params.permissions)?;
+  if !current_app.is_active(&env.block) {
+    return Ok(Some(authn.read_only()));
+  }
+  Ok(Some(authn))
+}
+
+/// proves that the application runs the code hash, which must be pinned.
+/// secret network rejects a query sent with a code hash the contract does not run.
+/// nothing is verified while no code hash is pinned.
+fn verify_code_hash(
+  deps: Deps,
+  pinned_code_hashes: &[String],
+  address: &Addr,
+  code_hash: &str,
+) -> Result<bool, ContractError> {
+  if pinned_code_hashes.is_empty() {
+    return Ok(false);
+  }
+  if !pinned_code_hashes.iter().any(|h| h == code_hash) {
+    return Err(ContractError::Unauthorized(
+      "code hash is not pinned".into(),
+    ));
+  }
+  let _: msg::ApplicationQueryAnswer = msg::ApplicationQueryMsg::Ping {}
+    .query(deps.querier, code_hash.to_string(), address.to_string())
+    .map_err(|_| ContractError::Unauthorized("code hash mismatch".into()))?;
+  Ok(true)
+}
+
+/// scopes an application needs to send the message.
+fn application_scopes(msg: &msg::ExecuteMsg) -> Vec<msg::ApplicationScope> {
+  fn op_scope(op: &msg::Op) -> msg::ApplicationScope {
+    match op {
+      msg::Op::Store(_) => msg::ApplicationScope::Store,
+      msg::Op::UpdateData(_) => msg::ApplicationScope::Update,
+      msg::Op::UpdateAuthz(_) => msg::ApplicationScope::UpdateAuthz,
+      msg::Op::Delete(_) => msg::ApplicationScope::Delete,
+    }
+  }
+  match msg {
+    msg::ExecuteMsg::Store(_) => vec![msg::ApplicationScope::Store],
+    msg::ExecuteMsg::UpdateData(_) | msg::ExecuteMsg::Rollback(_) => {
+      vec![msg::ApplicationScope::Update]
+    }
+    msg::ExecuteMsg::UpdateAuthz(_)
+    | msg::ExecuteMsg::TransferEntry(_)
+    | msg::ExecuteMsg::AcceptEntry(_) => vec![msg::ApplicationScope::UpdateAuthz],
+    msg::ExecuteMsg::Delete(_) => vec![msg::ApplicationScope::Delete],
+    msg::ExecuteMsg::CreateGroup(_)
+    | msg::ExecuteMsg::AddGroupMember(_)
+    | msg::ExecuteMsg::RemoveGroupMember(_)
+    | msg::ExecuteMsg::DeleteGroup(_)
+    | msg::ExecuteMsg::RevokePermit(_)
+    | msg::ExecuteMsg::SetConsent(_) => vec![msg::ApplicationScope::Manage],
+    msg::ExecuteMsg::Batch(m) => m.ops.iter().map(op_scope).collect(),
+    _ => vec![],
+  }
 }
 
 #[entry_point]
@@ -46,66 +163,576 @@ pub fn instantiate(
   deps: DepsMut,
   env: Env,
   info: MessageInfo,
-  _msg: msg::InstantiateMsg,
-) -> StdResult<Response> {
+  msg: msg::InstantiateMsg,
+) -> Result<Response, ContractError> {
+  viewing_key::init_seed(deps.storage, &env, &info, msg.entropy.as_str())?;
+  let owner_address = match msg.owner {
+    Some(s) => deps.api.addr_validate(s.as_str())?,
+    None => info.sender,
+  };
+  let pinned_code_hashes = msg.pinned_code_hashes.unwrap_or_default();
+  let mut applications: Vec<Application> = vec![];
+  for m in msg.applications.unwrap_or_default() {
+    let address = deps.api.addr_validate(m.address.as_str())?;
+    if applications.iter().any(|a| a.address == address) {
+      return Err(ContractError::AlreadyExists("application".into()));
+    }
+    let verified = verify_code_hash(deps.as_ref(), &pinned_code_hashes, &address, &m.code_hash)?;
+    applications.push(Application {
+      address,
+      code_hash: m.code_hash,
+      version: m.version,
+      added_at: env.block.time,
+      state: m.state.unwrap_or(msg::ApplicationState::Active),
+      scopes: m.scopes.unwrap_or_else(msg::ApplicationScope::all),
+      verified,
+    });
+  }
+  let history_depth = msg.history_depth.unwrap_or(0);
+  ContractConfig::check_history_depth(history_depth)?;
   let config = ContractConfig {
     my_address: env.contract.address,
-    owner_address: info.sender,
-    applications_address: Vec::new(),
+    owner_address: Some(owner_address),
+    pending_owner_address: None,
+    applications_address: vec![],
+    applications,
+    history_depth,
+    caller_policy: msg.caller_policy.unwrap_or_default(),
+    pinned_code_hashes,
+    trust_list_frozen: false,
+    privacy_mode: msg.privacy_mode.unwrap_or(false),
+    padding_block_size: msg
+      .padding_block_size
+      .unwrap_or(defs::PADDING_BLOCK_SIZE_DEFAULT),
   };
   config.save(deps.storage)?;
 
   Ok(Response::default())
 }
 
+/// pads with spaces to a multiple of the block size. 0 disables padding.
+fn pad(mut data: Binary, block_size: u32) -> Binary {
+  if block_size > 0 {
+    space_pad(&mut data.0, block_size as usize);
+  }
+  data
+}
+
 #[entry_point]
 pub fn execute(
   deps: DepsMut,
   env: Env,
   info: MessageInfo,
   msg: msg::ExecuteMsg,
-) -> StdResult<Response> {
+) -> Result<Response, ContractError> {
   let config = ContractConfig::load(deps.storage)?;
+  let block_size = config.padding_block_size;
+  let mut res = execute_msg(deps, env, info, config, msg)?;
+  res.data = res.data.map(|d| pad(d, block_size));
+  Ok(res)
+}
+
+fn execute_msg(
+  deps: DepsMut,
+  env: Env,
+  info: MessageInfo,
+  config: ContractConfig,
+  msg: msg::ExecuteMsg,
+) -> Result<Response, ContractError> {
+  // owner messages, housekeeping and viewing keys (bound to the sender)
+  // are not subject to the caller policy.
   match msg {
-    msg::ExecuteMsg::SetApplications(m) => {
+    msg::ExecuteMsg::AddApplication(_)
+    | msg::ExecuteMsg::UpdateApplication(_)
+    | msg::ExecuteMsg::RemoveApplication(_)
+    | msg::ExecuteMsg::VerifyApplication(_)
+    | msg::ExecuteMsg::PinCodeHash(_)
+    | msg::ExecuteMsg::UnpinCodeHash(_)
+    | msg::ExecuteMsg::FreezeTrustList(_)
+    | msg::ExecuteMsg::ProposeOwner(_)
+    | msg::ExecuteMsg::AcceptOwner(_)
+    | msg::ExecuteMsg::RenounceOwnership(_)
+    | msg::ExecuteMsg::SetHistoryDepth(_)
+    | msg::ExecuteMsg::SetCallerPolicy(_)
+    | msg::ExecuteMsg::SetPrivacyMode(_)
+    | msg::ExecuteMsg::SetPaddingBlockSize(_)
+    | msg::ExecuteMsg::MigrateLegacy(_)
+    | msg::ExecuteMsg::PurgeExpired(_)
+    | msg::ExecuteMsg::CreateViewingKey(_)
+    | msg::ExecuteMsg::SetViewingKey(_) => {}
+    _ => config.check_caller(&info.sender, &env.block)?,
+  }
+  if let Some(app) = config.application(&info.sender) {
+    for scope in application_scopes(&msg) {
+      app.check_scope(scope)?;
+    }
+  }
+  match msg {
+    msg::ExecuteMsg::AddApplication(m) => {
+      config.check_owner(&info.sender)?;
+      let address = deps.api.addr_validate(m.address.as_str())?;
+      if config.application(&address).is_some() {
+        return Err(ContractError::AlreadyExists("application".into()));
+      }
+      let verified = verify_code_hash(
+        deps.as_ref(),
+        &config.pinned_code_hashes,
+        &address,
+        &m.code_hash,
+      )?;
+      let mut c = config.clone();
+      c.applications.push(Application {
+        address,
+        code_hash: m.code_hash,
+        version: m.version,
+        added_at: env.block.time,
+        state: m.state.unwrap_or(msg::ApplicationState::Active),
+        scopes: m.scopes.unwrap_or_else(msg::ApplicationScope::all),
+        verified,
+      });
+      c.save(deps.storage)?;
+      Ok(Response::new())
+    }
+    msg::ExecuteMsg::UpdateApplication(m) => {
+      config.check_owner(&info.sender)?;
+      let address = deps.api.addr_validate(m.address.as_str())?;
+      let mut c = config.clone();
+      let app = c.applications.iter_mut().find(|a| a.address == address);
+      if app.is_none() {
+        return Err(ContractError::NotFound("application".into()));
+      }
+      let app = app.unwrap();
+      if let Some(code_hash) = m.code_hash {
+        app.verified = verify_code_hash(
+          deps.as_ref(),
+          &config.pinned_code_hashes,
+          &address,
+          &code_hash,
+        )?;
+        app.code_hash = code_hash;
+      }
+      if let Some(version) = m.version {
+        app.version = version;
+      }
+      if let Some(state) = m.state {
+        app.state = state;
+      }
+      if let Some(scopes) = m.scopes {
+        app.scopes = scopes;
+      }
+      c.save(deps.storage)?;
+      Ok(Response::new())
+    }
+    msg::ExecuteMsg::RemoveApplication(m) => {
+      config.check_owner(&info.sender)?;
+      let address = deps.api.addr_validate(m.address.as_str())?;
+      if config.application(&address).is_none() {
+        return Err(ContractError::NotFound("application".into()));
+      }
       let mut c = config.clone();
-      c.applications_address = m
+      c.applications.retain(|a| a.address != address);
+      c.save(deps.storage)?;
+      Ok(Response::new())
+    }
+    msg::ExecuteMsg::VerifyApplication(m) => {
+      let address = deps.api.addr_validate(m.address.as_str())?;
+      if config.application(&address).is_none() {
+        return Err(ContractError::NotFound("application".into()));
+      }
+      let mut c = config.clone();
+      let app = c
         .applications
-        .iter()
-        .map(|s| deps.api.addr_validate(s.as_str()))
-        .collect::<Result<Vec<_>, _>>()?;
+        .iter_mut()
+        .find(|a| a.address == address)
+        .unwrap();
+      app.verified = verify_code_hash(
+        deps.as_ref(),
+        &config.pinned_code_hashes,
+        &address,
+        &app.code_hash,
+      )?;
+      c.save(deps.storage)?;
+      Ok(Response::new())
+    }
+    msg::ExecuteMsg::PinCodeHash(m) => {
+      config.check_owner(&info.sender)?;
+      config.check_trust_list_not_frozen()?;
+      let mut c = config.clone();
+      if !c.pinned_code_hashes.contains(&m.code_hash) {
+        c.pinned_code_hashes.push(m.code_hash);
+      }
+      c.save(deps.storage)?;
+      Ok(Response::new())
+    }
+    msg::ExecuteMsg::UnpinCodeHash(m) => {
+      config.check_owner(&info.sender)?;
+      config.check_trust_list_not_frozen()?;
+      let mut c = config.clone();
+      c.pinned_code_hashes.retain(|h| h != &m.code_hash);
+      c.save(deps.storage)?;
+      Ok(Response::new())
+    }
+    msg::ExecuteMsg::FreezeTrustList(_m) => {
+      config.check_owner(&info.sender)?;
+      if config.pinned_code_hashes.is_empty() {
+        return Err(ContractError::InvalidRequest(
+          "no pinned code hashes".into(),
+        ));
+      }
+      let mut c = config.clone();
+      c.trust_list_frozen = true;
+      c.save(deps.storage)?;
+      Ok(Response::new())
+    }
+    msg::ExecuteMsg::ProposeOwner(m) => {
+      config.check_owner(&info.sender)?;
+      let mut c = config.clone();
+      c.pending_owner_address = Some(deps.api.addr_validate(m.owner.as_str())?);
+      c.save(deps.storage)?;
+      Ok(Response::new())
+    }
+    msg::ExecuteMsg::AcceptOwner(_m) => {
+      config.check_pending_owner(&info.sender)?;
+      let mut c = config.clone();
+      c.owner_address = c.pending_owner_address.take();
+      c.save(deps.storage)?;
+      Ok(Response::new())
+    }
+    msg::ExecuteMsg::RenounceOwnership(_m) => {
+      config.check_owner(&info.sender)?;
+      let mut c = config.clone();
+      c.owner_address = None;
+      c.pending_owner_address = None;
+      c.save(deps.storage)?;
+      Ok(Response::new())
+    }
+    msg::ExecuteMsg::SetHistoryDepth(m) => {
+      config.check_owner(&info.sender)?;
+      ContractConfig::check_history_depth(m.history_depth)?;
+      let mut c = config.clone();
+      c.history_depth = m.history_depth;
       c.save(deps.storage)?;
       Ok(Response::new())
     }
+    msg::ExecuteMsg::SetCallerPolicy(m) => {
+      config.check_owner(&info.sender)?;
+      let mut c = config.clone();
+      c.caller_policy = m.caller_policy;
+      c.save(deps.storage)?;
+      Ok(Response::new())
+    }
+    msg::ExecuteMsg::SetPrivacyMode(m) => {
+      config.check_owner(&info.sender)?;
+      let mut c = config.clone();
+      c.privacy_mode = m.privacy_mode;
+      c.save(deps.storage)?;
+      Ok(Response::new())
+    }
+    msg::ExecuteMsg::SetPaddingBlockSize(m) => {
+      config.check_owner(&info.sender)?;
+      let mut c = config.clone();
+      c.padding_block_size = m.padding_block_size;
+      c.save(deps.storage)?;
+      Ok(Response::new())
+    }
+    msg::ExecuteMsg::MigrateLegacy(m) => {
+      config.check_owner(&info.sender)?;
+      state::migrate_legacy(deps, env, info, m)
+    }
     msg::ExecuteMsg::Store(m) => {
-      let authn = authenticate(deps.as_ref(), &config, m.permit.as_ref())?;
-      state::store(deps, env, info, authn, m)
+      let authn = authenticate(
+        deps.as_ref(),
+        &env,
+        &config,
+        Some(&info.sender),
+        m.permit.as_ref(),
+        m.viewing_key.as_ref(),
+      )?;
+      state::store(deps, env, info, &config, authn, m)
     }
     msg::ExecuteMsg::UpdateData(m) => {
-      let authn = authenticate(deps.as_ref(), &config, m.permit.as_ref())?;
-      state::update_data(deps, env, info, authn, m)
+      let authn = authenticate(
+        deps.as_ref(),
+        &env,
+        &config,
+        Some(&info.sender),
+        m.permit.as_ref(),
+        m.viewing_key.as_ref(),
+      )?;
+      state::update_data(deps, env, info, &config, authn, m)
     }
     msg::ExecuteMsg::UpdateAuthz(m) => {
-      let authn = authenticate(deps.as_ref(), &config, m.permit.as_ref())?;
-      state::update_authz(deps, env, info, authn, m)
+      let authn = authenticate(
+        deps.as_ref(),
+        &env,
+        &config,
+        Some(&info.sender),
+        m.permit.as_ref(),
+        m.viewing_key.as_ref(),
+      )?;
+      state::update_authz(deps, env, info, &config, authn, m)
     }
     msg::ExecuteMsg::Delete(m) => {
-      let authn = authenticate(deps.as_ref(), &config, m.permit.as_ref())?;
-      state::delete(deps, env, info, authn, m)
+      let authn = authenticate(
+        deps.as_ref(),
+        &env,
+        &config,
+        Some(&info.sender),
+        m.permit.as_ref(),
+        m.viewing_key.as_ref(),
+      )?;
+      state::delete(deps, env, info, &config, authn, m)
+    }
+    msg::ExecuteMsg::TransferEntry(m) => {
+      let authn = authenticate(
+        deps.as_ref(),
+        &env,
+        &config,
+        Some(&info.sender),
+        m.permit.as_ref(),
+        m.viewing_key.as_ref(),
+      )?;
+      state::transfer_entry(deps, env, info, &config, authn, m)
+    }
+    msg::ExecuteMsg::AcceptEntry(m) => {
+      let authn = authenticate(
+        deps.as_ref(),
+        &env,
+        &config,
+        Some(&info.sender),
+        m.permit.as_ref(),
+        m.viewing_key.as_ref(),
+      )?;
+      state::accept_entry(deps, env, info, &config, authn, m)
+    }
+    msg::ExecuteMsg::Rollback(m) => {
+      let authn = authenticate(
+        deps.as_ref(),
+        &env,
+        &config,
+        Some(&info.sender),
+        m.permit.as_ref(),
+        m.viewing_key.as_ref(),
+      )?;
+      state::rollback(deps, env, info, &config, authn, m)
+    }
+    msg::ExecuteMsg::PurgeExpired(m) => state::purge_expired(deps, env, info, &config, m),
+    msg::ExecuteMsg::CreateGroup(m) => {
+      let authn = authenticate(
+        deps.as_ref(),
+        &env,
+        &config,
+        Some(&info.sender),
+        m.permit.as_ref(),
+        m.viewing_key.as_ref(),
+      )?;
+      group::create(deps, env, info, &config, authn, m)
+    }
+    msg::ExecuteMsg::AddGroupMember(m) => {
+      let authn = authenticate(
+        deps.as_ref(),
+        &env,
+        &config,
+        Some(&info.sender),
+        m.permit.as_ref(),
+        m.viewing_key.as_ref(),
+      )?;
+      group::add_member(deps, env, info, &config, authn, m)
+    }
+    msg::ExecuteMsg::RemoveGroupMember(m) => {
+      let authn = authenticate(
+        deps.as_ref(),
+        &env,
+        &config,
+        Some(&info.sender),
+        m.permit.as_ref(),
+        m.viewing_key.as_ref(),
+      )?;
+      group::remove_member(deps, env, info, &config, authn, m)
+    }
+    msg::ExecuteMsg::DeleteGroup(m) => {
+      let authn = authenticate(
+        deps.as_ref(),
+        &env,
+        &config,
+        Some(&info.sender),
+        m.permit.as_ref(),
+        m.viewing_key.as_ref(),
+      )?;
+      group::delete(deps, env, info, &config, authn, m)
+    }
+    msg::ExecuteMsg::CreateViewingKey(m) => viewing_key::create(deps, env, info, &config, m),
+    msg::ExecuteMsg::SetViewingKey(m) => viewing_key::set(deps, env, info, &config, m),
+    msg::ExecuteMsg::RevokePermit(m) => {
+      let authn = authenticate(
+        deps.as_ref(),
+        &env,
+        &config,
+        Some(&info.sender),
+        m.permit.as_ref(),
+        m.viewing_key.as_ref(),
+      )?;
+      authn::revoke_permit(deps, env, info, &config, authn, m)
+    }
+    msg::ExecuteMsg::SetConsent(m) => {
+      let authn = authenticate(
+        deps.as_ref(),
+        &env,
+        &config,
+        Some(&info.sender),
+        m.permit.as_ref(),
+        m.viewing_key.as_ref(),
+      )?;
+      consent::set(deps, env, info, &config, authn, m)
+    }
+    msg::ExecuteMsg::Batch(m) => {
+      let authn = authenticate(
+        deps.as_ref(),
+        &env,
+        &config,
+        Some(&info.sender),
+        m.permit.as_ref(),
+        m.viewing_key.as_ref(),
+      )?;
+      state::batch(deps, env, info, &config, authn, m)
     }
   }
 }
 
 #[entry_point]
-pub fn query(deps: Deps, env: Env, msg: msg::QueryMsg) -> StdResult<QueryResponse> {
+pub fn query(deps: Deps, env: Env, msg: msg::QueryMsg) -> Result<QueryResponse, ContractError> {
   let config = ContractConfig::load(deps.storage)?;
-  let r: StdResult<msg::QueryAnswer> = match msg {
+  let r: Result<msg::QueryAnswer, ContractError> = match msg {
     msg::QueryMsg::Get(m) => {
-      let authn = authenticate(deps, &config, m.permit.as_ref())?;
-      state::get(deps, env, authn, m)
+      let authn = authenticate(
+        deps,
+        &env,
+        &config,
+        None,
+        m.permit.as_ref(),
+        m.viewing_key.as_ref(),
+      )?;
+      state::get(deps, env, &config, authn, m)
+    }
+    msg::QueryMsg::GetMany(m) => {
+      let authn = authenticate(
+        deps,
+        &env,
+        &config,
+        None,
+        m.permit.as_ref(),
+        m.viewing_key.as_ref(),
+      )?;
+      state::get_many(deps, env, &config, authn, m)
+    }
+    msg::QueryMsg::GetMeta(m) => {
+      let authn = authenticate(
+        deps,
+        &env,
+        &config,
+        None,
+        m.permit.as_ref(),
+        m.viewing_key.as_ref(),
+      )?;
+      state::get_meta(deps, env, &config, authn, m)
+    }
+    msg::QueryMsg::Exists(m) => {
+      let authn = authenticate(
+        deps,
+        &env,
+        &config,
+        None,
+        m.permit.as_ref(),
+        m.viewing_key.as_ref(),
+      )?;
+      state::exists(deps, env, &config, authn, m)
+    }
+    msg::QueryMsg::List(m) => {
+      let authn = authenticate(
+        deps,
+        &env,
+        &config,
+        None,
+        m.permit.as_ref(),
+        m.viewing_key.as_ref(),
+      )?;
+      state::list(deps, env, &config, authn, m)
+    }
+    msg::QueryMsg::GetRevision(m) => {
+      let authn = authenticate(
+        deps,
+        &env,
+        &config,
+        None,
+        m.permit.as_ref(),
+        m.viewing_key.as_ref(),
+      )?;
+      state::get_revision(deps, env, &config, authn, m)
+    }
+    msg::QueryMsg::ListRevisions(m) => {
+      let authn = authenticate(
+        deps,
+        &env,
+        &config,
+        None,
+        m.permit.as_ref(),
+        m.viewing_key.as_ref(),
+      )?;
+      state::list_revisions(deps, env, &config, authn, m)
+    }
+    msg::QueryMsg::ChangesSince(m) => {
+      let authn = authenticate(
+        deps,
+        &env,
+        &config,
+        None,
+        m.permit.as_ref(),
+        m.viewing_key.as_ref(),
+      )?;
+      state::changes_since(deps, env, &config, authn, m)
+    }
+    msg::QueryMsg::GetGroup(m) => {
+      let authn = authenticate(
+        deps,
+        &env,
+        &config,
+        None,
+        m.permit.as_ref(),
+        m.viewing_key.as_ref(),
+      )?;
+      group::get(deps, env, &config, authn, m)
     }
+    msg::QueryMsg::RevokedPermits(m) => {
+      let authn = authenticate(
+        deps,
+        &env,
+        &config,
+        None,
+        m.permit.as_ref(),
+        m.viewing_key.as_ref(),
+      )?;
+      authn::revoked_permits(deps, env, &config, authn, m)
+    }
+    msg::QueryMsg::Consent(m) => {
+      let authn = authenticate(
+        deps,
+        &env,
+        &config,
+        None,
+        m.permit.as_ref(),
+        m.viewing_key.as_ref(),
+      )?;
+      consent::get(deps, env, &config, authn, m)
+    }
+    msg::QueryMsg::ListApplications(_m) => Ok(msg::QueryAnswer::Applications(
+      config.applications.iter().map(|a| a.to_output()).collect(),
+    )),
+    msg::QueryMsg::TrustList(_m) => Ok(msg::QueryAnswer::TrustList(msg::TrustListOutput {
+      code_hashes: config.pinned_code_hashes.clone(),
+      frozen: config.trust_list_frozen,
+    })),
   };
-  r.and_then(|a| to_binary(&a))
+  Ok(pad(to_binary(&r?)?, config.padding_block_size))
 }
 /*
 pub fn try_submit_net_worth(
@@ -174,8 +801,483 @@ fn query_who_is_richer(deps: Deps) -> StdResult<RicherResponse> {
 mod tests {
   use super::*;
 
-  use cosmwasm_std::coins;
   use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+  use cosmwasm_std::{coins, from_binary};
+
+  type MockDeps = cosmwasm_std::OwnedDeps<
+    cosmwasm_std::MemoryStorage,
+    cosmwasm_std::testing::MockApi,
+    cosmwasm_std::testing::MockQuerier,
+  >;
+
+  fn setup_padding(padding_block_size: u32) -> MockDeps {
+    let mut msg = instantiate_msg();
+    msg.padding_block_size = Some(padding_block_size);
+    let mut deps = setup_with(msg);
+    for (key, len) in [("short", 1), ("long", 30)] {
+      let msg = msg::ExecuteMsg::Store(msg::ExecuteStore {
+        permit: None,
+        viewing_key: Some(viewing_key()),
+        key: key.to_string(),
+        version: "1".to_string(),
+        data: vec![0; len],
+        authz: msg::Authz::new("", false),
+        expires_at: None,
+      });
+      execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg).unwrap();
+    }
+    deps
+  }
+
+  fn viewing_key() -> msg::ViewingKey {
+    msg::ViewingKey {
+      address: "alice".to_string(),
+      key: "key".to_string(),
+    }
+  }
+
+  fn query_len(deps: Deps, key: &str) -> usize {
+    let msg = msg::QueryMsg::Get(msg::QueryGet {
+      permit: None,
+      viewing_key: Some(viewing_key()),
+      owner: None,
+      key: key.to_string(),
+    });
+    query(deps, mock_env(), msg).unwrap().len()
+  }
+
+  #[test]
+  fn query_answers_are_padded_uniformly() {
+    let deps = setup_padding(1024);
+    assert_eq!(query_len(deps.as_ref(), "short"), 1024);
+    assert_eq!(query_len(deps.as_ref(), "long"), 1024);
+    assert_eq!(query_len(deps.as_ref(), "missing"), 1024);
+  }
+
+  #[test]
+  fn response_data_is_padded_uniformly() {
+    let mut deps = setup_padding(1024);
+    let mut lens = vec![];
+    for entropy in ["", "a much longer entropy than the other one"] {
+      let msg = msg::ExecuteMsg::CreateViewingKey(msg::ExecuteCreateViewingKey {
+        entropy: entropy.to_string(),
+      });
+      let res = execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), msg).unwrap();
+      lens.push(res.data.unwrap().len());
+    }
+    for keys in [vec!["short"], vec!["short", "long"]] {
+      let ops = keys
+        .iter()
+        .map(|k| {
+          msg::Op::UpdateData(msg::ExecuteUpdateData {
+            permit: None,
+            viewing_key: None,
+            owner: None,
+            key: k.to_string(),
+            version: "1".to_string(),
+            data: vec![1],
+            expires_at: None,
+            expected_revision: None,
+          })
+        })
+        .collect();
+      let msg = msg::ExecuteMsg::Batch(msg::ExecuteBatch {
+        permit: None,
+        viewing_key: Some(viewing_key()),
+        ops,
+      });
+      let res = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg).unwrap();
+      lens.push(res.data.unwrap().len());
+    }
+    assert_eq!(lens, vec![1024; 4]);
+  }
+
+  #[test]
+  fn padding_can_be_disabled() {
+    let deps = setup_padding(0);
+    assert!(query_len(deps.as_ref(), "short") < query_len(deps.as_ref(), "long"));
+  }
+
+  fn instantiate_msg() -> msg::InstantiateMsg {
+    msg::InstantiateMsg {
+      owner: None,
+      applications: None,
+      history_depth: None,
+      caller_policy: None,
+      pinned_code_hashes: None,
+      privacy_mode: None,
+      padding_block_size: None,
+      entropy: "entropy".to_string(),
+    }
+  }
+
+  /// instantiated by admin. alice, bob and carol have the viewing key "key".
+  fn setup_with(msg: msg::InstantiateMsg) -> MockDeps {
+    let mut deps = mock_dependencies();
+    instantiate(deps.as_mut(), mock_env(), mock_info("admin", &[]), msg).unwrap();
+    for account in ["alice", "bob", "carol"] {
+      let msg = msg::ExecuteMsg::SetViewingKey(msg::ExecuteSetViewingKey {
+        key: "key".to_string(),
+      });
+      execute(deps.as_mut(), mock_env(), mock_info(account, &[]), msg).unwrap();
+    }
+    deps
+  }
+
+  fn setup() -> MockDeps {
+    setup_with(instantiate_msg())
+  }
+
+  fn vk(account: &str) -> Option<msg::ViewingKey> {
+    Some(msg::ViewingKey {
+      address: account.to_string(),
+      key: "key".to_string(),
+    })
+  }
+
+  fn exec(
+    deps: &mut MockDeps,
+    sender: &str,
+    msg: msg::ExecuteMsg,
+  ) -> Result<Response, ContractError> {
+    execute(deps.as_mut(), mock_env(), mock_info(sender, &[]), msg)
+  }
+
+  fn attr(res: &Response, key: &str) -> String {
+    let a = res.attributes.iter().find(|a| a.key == key);
+    a.unwrap().value.clone()
+  }
+
+  fn store(
+    deps: &mut MockDeps,
+    account: &str,
+    key: &str,
+    authz: msg::Authz,
+  ) -> Result<Response, ContractError> {
+    let msg = msg::ExecuteMsg::Store(msg::ExecuteStore {
+      permit: None,
+      viewing_key: vk(account),
+      key: key.to_string(),
+      version: "1".to_string(),
+      data: vec![1],
+      authz,
+      expires_at: None,
+    });
+    exec(deps, account, msg)
+  }
+
+  fn get(
+    deps: &MockDeps,
+    account: &str,
+    owner: &str,
+    key: &str,
+  ) -> Result<Option<msg::DataOutput>, ContractError> {
+    let msg = msg::QueryMsg::Get(msg::QueryGet {
+      permit: None,
+      viewing_key: vk(account),
+      owner: Some(owner.to_string()),
+      key: key.to_string(),
+    });
+    match from_binary(&query(deps.as_ref(), mock_env(), msg)?)? {
+      msg::QueryAnswer::Data(d) => Ok(d),
+      a => panic!("unexpected answer {:?}", a),
+    }
+  }
+
+  fn store_op(key: &str) -> msg::Op {
+    msg::Op::Store(msg::ExecuteStore {
+      permit: None,
+      viewing_key: None,
+      key: key.to_string(),
+      version: "1".to_string(),
+      data: vec![1],
+      authz: msg::Authz::new("", false),
+      expires_at: None,
+    })
+  }
+
+  fn delete_op(key: &str) -> msg::Op {
+    msg::Op::Delete(msg::ExecuteDelete {
+      permit: None,
+      viewing_key: None,
+      key: key.to_string(),
+      expected_revision: None,
+    })
+  }
+
+  fn batch(
+    deps: &mut MockDeps,
+    account: &str,
+    ops: Vec<msg::Op>,
+  ) -> Result<Response, ContractError> {
+    let msg = msg::ExecuteMsg::Batch(msg::ExecuteBatch {
+      permit: None,
+      viewing_key: vk(account),
+      ops,
+    });
+    exec(deps, account, msg)
+  }
+
+  #[test]
+  fn batch_applies_every_operation() {
+    let mut deps = setup();
+    store(&mut deps, "alice", "old", msg::Authz::new("", false)).unwrap();
+    let res = batch(
+      &mut deps,
+      "alice",
+      vec![store_op("a"), store_op("b"), delete_op("old")],
+    )
+    .unwrap();
+    assert!(res.attributes.is_empty());
+    let answer: msg::ExecuteAnswer = from_binary(&res.data.unwrap()).unwrap();
+    let results = match answer {
+      msg::ExecuteAnswer::Batch(results) => results
+        .into_iter()
+        .map(|r| (r.key, r.revision, r.seq))
+        .collect::<Vec<_>>(),
+      a => panic!("unexpected answer {:?}", a),
+    };
+    assert_eq!(
+      results,
+      vec![
+        ("a".to_string(), Some(1), Some(2)),
+        ("b".to_string(), Some(1), Some(3)),
+        ("old".to_string(), None, Some(4)),
+      ]
+    );
+    assert!(get(&deps, "alice", "alice", "a").unwrap().is_some());
+    assert!(get(&deps, "alice", "alice", "b").unwrap().is_some());
+    assert!(get(&deps, "alice", "alice", "old").unwrap().is_none());
+  }
+
+  /// a failing operation fails the whole execute, which the chain reverts,
+  /// so the operations before it are not applied either.
+  #[test]
+  fn batch_fails_if_any_operation_fails() {
+    let mut deps = setup();
+    store(&mut deps, "alice", "b", msg::Authz::new("", false)).unwrap();
+    let err = batch(&mut deps, "alice", vec![store_op("a"), store_op("b")]).unwrap_err();
+    assert_eq!(err, ContractError::AlreadyExists("op 1: entry".into()));
+    let err = batch(&mut deps, "alice", vec![delete_op("missing")]).unwrap_err();
+    assert_eq!(err, ContractError::NotFound("op 0: entry".into()));
+    assert!(batch(&mut deps, "alice", vec![store_op("c")]).is_ok());
+    let msg = msg::ExecuteMsg::Batch(msg::ExecuteBatch {
+      permit: None,
+      viewing_key: None,
+      ops: vec![store_op("d")],
+    });
+    assert!(matches!(
+      exec(&mut deps, "alice", msg),
+      Err(ContractError::Unauthorized(_))
+    ));
+  }
+
+  fn update(
+    deps: &mut MockDeps,
+    account: &str,
+    owner: &str,
+    key: &str,
+    expected_revision: Option<u64>,
+  ) -> Result<Response, ContractError> {
+    let msg = msg::ExecuteMsg::UpdateData(msg::ExecuteUpdateData {
+      permit: None,
+      viewing_key: vk(account),
+      owner: Some(owner.to_string()),
+      key: key.to_string(),
+      version: "1".to_string(),
+      data: vec![2],
+      expires_at: None,
+      expected_revision,
+    });
+    exec(deps, account, msg)
+  }
+
+  fn delete(deps: &mut MockDeps, account: &str, key: &str) -> Result<Response, ContractError> {
+    batch(deps, account, vec![delete_op(key)])
+  }
+
+  #[test]
+  fn revisions_are_not_reused_after_delete() {
+    let mut deps = setup();
+    let res = store(&mut deps, "alice", "k", msg::Authz::new("", false)).unwrap();
+    assert_eq!(attr(&res, "revision"), "1");
+    update(&mut deps, "alice", "alice", "k", Some(1)).unwrap();
+    delete(&mut deps, "alice", "k").unwrap();
+
+    let res = store(&mut deps, "alice", "k", msg::Authz::new("", false)).unwrap();
+    assert_eq!(attr(&res, "revision"), "3");
+    let err = update(&mut deps, "alice", "alice", "k", Some(1)).unwrap_err();
+    assert!(matches!(err, ContractError::Conflict(_)));
+    update(&mut deps, "alice", "alice", "k", Some(3)).unwrap();
+    assert_eq!(
+      get(&deps, "alice", "alice", "k").unwrap().unwrap().revision,
+      4
+    );
+  }
+
+  fn update_authz(
+    deps: &mut MockDeps,
+    account: &str,
+    key: &str,
+    authz: msg::Authz,
+  ) -> Result<Response, ContractError> {
+    let msg = msg::ExecuteMsg::UpdateAuthz(msg::ExecuteUpdateAuthz {
+      permit: None,
+      viewing_key: vk(account),
+      key: key.to_string(),
+      authz,
+      expected_revision: None,
+    });
+    exec(deps, account, msg)
+  }
+
+  fn revisions(deps: &MockDeps, account: &str, key: &str) -> Vec<u64> {
+    let msg = msg::QueryMsg::ListRevisions(msg::QueryListRevisions {
+      permit: None,
+      viewing_key: vk(account),
+      owner: None,
+      key: key.to_string(),
+    });
+    match from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap() {
+      msg::QueryAnswer::Revisions(revs) => revs.into_iter().map(|r| r.revision).collect(),
+      a => panic!("unexpected answer {:?}", a),
+    }
+  }
+
+  #[test]
+  fn history_keeps_every_revision_and_evicts_beyond_depth() {
+    let mut msg = instantiate_msg();
+    msg.history_depth = Some(3);
+    let mut deps = setup_with(msg);
+    store(&mut deps, "alice", "k", msg::Authz::new("", false)).unwrap();
+    update(&mut deps, "alice", "alice", "k", None).unwrap();
+    update_authz(&mut deps, "alice", "k", msg::Authz::new("", true)).unwrap();
+    assert_eq!(revisions(&deps, "alice", "k"), vec![3, 2, 1]);
+    update(&mut deps, "alice", "alice", "k", None).unwrap();
+    assert_eq!(revisions(&deps, "alice", "k"), vec![4, 3, 2]);
+
+    let msg = msg::ExecuteMsg::SetHistoryDepth(msg::ExecuteSetHistoryDepth { history_depth: 1 });
+    exec(&mut deps, "admin", msg).unwrap();
+    assert_eq!(revisions(&deps, "alice", "k"), vec![4]);
+    update(&mut deps, "alice", "alice", "k", None).unwrap();
+    delete(&mut deps, "alice", "k").unwrap();
+    store(&mut deps, "alice", "k", msg::Authz::new("", false)).unwrap();
+    assert_eq!(revisions(&deps, "alice", "k"), vec![6]);
+
+    let history = cosmwasm_storage::ReadonlyBucket::<crate::history::Revision>::multilevel(
+      &deps.storage,
+      &[defs::HISTORY_BUCKET_KEY, b"alice", b"k"],
+    );
+    for r in 1..6u64 {
+      assert!(history.may_load(&r.to_be_bytes()).unwrap().is_none());
+    }
+  }
+
+  fn purge(deps: &mut MockDeps, env: Env, limit: Option<u32>) -> String {
+    let msg = msg::ExecuteMsg::PurgeExpired(msg::ExecutePurgeExpired { limit });
+    let res = execute(deps.as_mut(), env, mock_info("anyone", &[]), msg).unwrap();
+    attr(&res, "purged")
+  }
+
+  #[test]
+  fn purge_expired_removes_expired_entries_first() {
+    let mut deps = setup();
+    let env = mock_env();
+    let expirations = [
+      ("a", Some(msg::Expiration::AtHeight(env.block.height + 5))),
+      ("b", Some(msg::Expiration::AtHeight(env.block.height + 2))),
+      (
+        "c",
+        Some(msg::Expiration::AtTime(env.block.time.plus_seconds(10))),
+      ),
+      ("d", None),
+    ];
+    for (key, expires_at) in expirations {
+      let msg = msg::ExecuteMsg::Store(msg::ExecuteStore {
+        permit: None,
+        viewing_key: vk("alice"),
+        key: key.to_string(),
+        version: "1".to_string(),
+        data: vec![1],
+        authz: msg::Authz::new("", false),
+        expires_at,
+      });
+      exec(&mut deps, "alice", msg).unwrap();
+    }
+    let msg = msg::ExecuteMsg::UpdateData(msg::ExecuteUpdateData {
+      permit: None,
+      viewing_key: vk("alice"),
+      owner: None,
+      key: "a".to_string(),
+      version: "1".to_string(),
+      data: vec![2],
+      expires_at: Some(msg::Expiration::AtHeight(env.block.height + 1)),
+      expected_revision: None,
+    });
+    exec(&mut deps, "alice", msg).unwrap();
+
+    let mut later = mock_env();
+    later.block.height += 3;
+    assert_eq!(purge(&mut deps, later.clone(), None), "2");
+    assert_eq!(purge(&mut deps, later.clone(), None), "0");
+
+    later.block.time = later.block.time.plus_seconds(10);
+    assert_eq!(purge(&mut deps, later.clone(), Some(1)), "1");
+    assert_eq!(purge(&mut deps, later, None), "0");
+    assert!(get(&deps, "alice", "alice", "d").unwrap().is_some());
+  }
+
+  /// an entry of the global bucket, as written before per-owner namespaces.
+  #[derive(serde::Serialize, serde::Deserialize)]
+  struct LegacyData {
+    version: String,
+    data: Vec<u8>,
+    authz: msg::Authz,
+  }
+
+  #[test]
+  fn migrate_legacy_moves_entries_to_their_owner() {
+    let mut deps = setup();
+    for (key, owner) in [("moved", "alice"), ("taken", "alice"), ("bad", "Not Valid")] {
+      let legacy = LegacyData {
+        version: "1".to_string(),
+        data: vec![7],
+        authz: msg::Authz::new(owner, false),
+      };
+      cosmwasm_storage::bucket(&mut deps.storage, defs::LEGACY_DATA_BUCKET_KEY)
+        .save(key.as_bytes(), &legacy)
+        .unwrap();
+    }
+    store(&mut deps, "alice", "taken", msg::Authz::new("", false)).unwrap();
+
+    let msg = msg::ExecuteMsg::MigrateLegacy(msg::ExecuteMigrateLegacy {
+      keys: vec![
+        "bad".into(),
+        "moved".into(),
+        "taken".into(),
+        "missing".into(),
+      ],
+    });
+    assert!(exec(&mut deps, "alice", msg.clone()).is_err());
+    let res = exec(&mut deps, "admin", msg).unwrap();
+    assert_eq!(attr(&res, "migrated"), "1");
+    assert_eq!(attr(&res, "skipped"), "1");
+    assert_eq!(attr(&res, "failed"), "1");
+
+    assert_eq!(
+      get(&deps, "alice", "alice", "moved").unwrap().unwrap().data,
+      vec![7]
+    );
+    assert_eq!(
+      get(&deps, "alice", "alice", "taken").unwrap().unwrap().data,
+      vec![1]
+    );
+    let legacy =
+      cosmwasm_storage::bucket_read::<LegacyData>(&deps.storage, defs::LEGACY_DATA_BUCKET_KEY);
+    assert!(legacy.may_load(b"moved").unwrap().is_none());
+    assert!(legacy.may_load(b"taken").unwrap().is_some());
+    assert!(legacy.may_load(b"bad").unwrap().is_some());
+  }
   /*
     #[test]
     fn proper_instantialization() {
@@ -257,4 +1359,1492 @@ mod tests {
       assert_eq!(&value.richer, "carol")
     }
   */
+
+  fn transfer(
+    deps: &mut MockDeps,
+    account: &str,
+    key: &str,
+    new_owner: Option<&str>,
+  ) -> Result<Response, ContractError> {
+    let msg = msg::ExecuteMsg::TransferEntry(msg::ExecuteTransferEntry {
+      permit: None,
+      viewing_key: vk(account),
+      key: key.to_string(),
+      new_owner: new_owner.map(|s| s.to_string()),
+      expected_revision: None,
+    });
+    exec(deps, account, msg)
+  }
+
+  fn accept(
+    deps: &mut MockDeps,
+    account: &str,
+    owner: &str,
+    key: &str,
+  ) -> Result<Response, ContractError> {
+    let msg = msg::ExecuteMsg::AcceptEntry(msg::ExecuteAcceptEntry {
+      permit: None,
+      viewing_key: vk(account),
+      owner: owner.to_string(),
+      key: key.to_string(),
+    });
+    exec(deps, account, msg)
+  }
+
+  #[test]
+  fn transfer_moves_entry_once_accepted() {
+    let mut deps = setup();
+    store(&mut deps, "alice", "k", msg::Authz::new("", false)).unwrap();
+    let not_pending = ContractError::Unauthorized("not a pending owner".into());
+    assert_eq!(
+      accept(&mut deps, "bob", "alice", "k").unwrap_err(),
+      not_pending
+    );
+
+    transfer(&mut deps, "alice", "k", Some("bob")).unwrap();
+    assert_eq!(
+      accept(&mut deps, "carol", "alice", "k").unwrap_err(),
+      not_pending
+    );
+    let res = accept(&mut deps, "bob", "alice", "k").unwrap();
+    assert_eq!(attr(&res, "revision"), "2");
+
+    assert_eq!(get(&deps, "alice", "alice", "k").unwrap(), None);
+    let data = get(&deps, "bob", "bob", "k").unwrap().unwrap();
+    assert_eq!(data.authz.owner(), "bob");
+    assert_eq!(data.pending_owner, None);
+    let res = store(&mut deps, "alice", "k", msg::Authz::new("", false)).unwrap();
+    assert_eq!(attr(&res, "revision"), "2");
+  }
+
+  #[test]
+  fn transfer_can_be_cancelled_and_not_accepted_over_an_entry() {
+    let mut deps = setup();
+    store(&mut deps, "alice", "k", msg::Authz::new("", false)).unwrap();
+    transfer(&mut deps, "alice", "k", Some("bob")).unwrap();
+    transfer(&mut deps, "alice", "k", None).unwrap();
+    assert_eq!(
+      accept(&mut deps, "bob", "alice", "k").unwrap_err(),
+      ContractError::Unauthorized("not a pending owner".into())
+    );
+
+    let err = transfer(&mut deps, "bob", "k", Some("carol")).unwrap_err();
+    assert_eq!(err, ContractError::NotFound("entry".into()));
+
+    store(&mut deps, "bob", "k", msg::Authz::new("", false)).unwrap();
+    transfer(&mut deps, "alice", "k", Some("bob")).unwrap();
+    assert_eq!(
+      accept(&mut deps, "bob", "alice", "k").unwrap_err(),
+      ContractError::AlreadyExists("entry".into())
+    );
+  }
+
+  fn update_with_expiry(
+    deps: &mut MockDeps,
+    account: &str,
+    expires_at: Option<msg::Expiration>,
+  ) -> Result<Response, ContractError> {
+    let msg = msg::ExecuteMsg::UpdateData(msg::ExecuteUpdateData {
+      permit: None,
+      viewing_key: vk(account),
+      owner: Some("alice".to_string()),
+      key: "k".to_string(),
+      version: "1".to_string(),
+      data: vec![2],
+      expires_at,
+      expected_revision: None,
+    });
+    exec(deps, account, msg)
+  }
+
+  #[test]
+  fn writers_update_data_but_only_owner_changes_expiration() {
+    let mut deps = setup();
+    let authz = msg::Authz::new("", false).update_writer("bob", true);
+    store(&mut deps, "alice", "k", authz).unwrap();
+    let expires_at = Some(msg::Expiration::AtHeight(mock_env().block.height + 10));
+
+    update(&mut deps, "bob", "alice", "k", Some(1)).unwrap();
+    assert_eq!(
+      update(&mut deps, "carol", "alice", "k", None).unwrap_err(),
+      ContractError::Unauthorized("not a writer".into())
+    );
+    assert_eq!(
+      update_with_expiry(&mut deps, "bob", expires_at.clone()).unwrap_err(),
+      ContractError::Unauthorized("not a owner".into())
+    );
+
+    update_with_expiry(&mut deps, "alice", expires_at.clone()).unwrap();
+    let never = Some(msg::Expiration::Never);
+    assert_eq!(
+      update_with_expiry(&mut deps, "bob", never.clone()).unwrap_err(),
+      ContractError::Unauthorized("not a owner".into())
+    );
+    update_with_expiry(&mut deps, "bob", None).unwrap();
+    update_with_expiry(&mut deps, "bob", expires_at.clone()).unwrap();
+    update_with_expiry(&mut deps, "alice", None).unwrap();
+    let data = get(&deps, "alice", "alice", "k").unwrap().unwrap();
+    assert_eq!(data.expires_at, expires_at);
+    assert_eq!(data.revision, 6);
+
+    update_with_expiry(&mut deps, "alice", never).unwrap();
+    let data = get(&deps, "alice", "alice", "k").unwrap().unwrap();
+    assert_eq!(data.expires_at, None);
+    let mut block = mock_env().block;
+    block.height += 10;
+    assert!(crate::expiry::expired(&deps.storage, &block, 10)
+      .unwrap()
+      .is_empty());
+
+    let authz = msg::Authz::new("", true);
+    let err = update_authz(&mut deps, "bob", "k", authz).unwrap_err();
+    assert_eq!(err, ContractError::NotFound("entry".into()));
+    let err = delete(&mut deps, "bob", "k").unwrap_err();
+    assert_eq!(err, ContractError::NotFound("op 0: entry".into()));
+  }
+
+  fn create_group(deps: &mut MockDeps, account: &str, members: &[&str]) -> u64 {
+    let msg = msg::ExecuteMsg::CreateGroup(msg::ExecuteCreateGroup {
+      permit: None,
+      viewing_key: vk(account),
+      name: "g".to_string(),
+      members: members.iter().map(|m| m.to_string()).collect(),
+    });
+    attr(&exec(deps, account, msg).unwrap(), "group_id")
+      .parse()
+      .unwrap()
+  }
+
+  #[test]
+  fn group_members_read_entries_of_the_group_owner() {
+    let mut deps = setup();
+    let id = create_group(&mut deps, "alice", &["bob"]);
+    let authz = msg::Authz::new("", false).update_reader_group(id, true);
+    store(&mut deps, "alice", "k", authz).unwrap();
+    let not_reader = ContractError::Unauthorized("not a reader".into());
+    assert!(get(&deps, "bob", "alice", "k").unwrap().is_some());
+    assert_eq!(get(&deps, "carol", "alice", "k").unwrap_err(), not_reader);
+
+    let msg = msg::ExecuteMsg::AddGroupMember(msg::ExecuteAddGroupMember {
+      permit: None,
+      viewing_key: vk("alice"),
+      id,
+      member: "carol".to_string(),
+    });
+    exec(&mut deps, "alice", msg).unwrap();
+    let msg = msg::ExecuteMsg::RemoveGroupMember(msg::ExecuteRemoveGroupMember {
+      permit: None,
+      viewing_key: vk("alice"),
+      id,
+      member: "bob".to_string(),
+    });
+    exec(&mut deps, "alice", msg).unwrap();
+    assert_eq!(get(&deps, "bob", "alice", "k").unwrap_err(), not_reader);
+    assert!(get(&deps, "carol", "alice", "k").unwrap().is_some());
+
+    let msg = msg::ExecuteMsg::DeleteGroup(msg::ExecuteDeleteGroup {
+      permit: None,
+      viewing_key: vk("bob"),
+      id,
+    });
+    let err = exec(&mut deps, "bob", msg).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized("not a owner".into()));
+    let msg = msg::ExecuteMsg::DeleteGroup(msg::ExecuteDeleteGroup {
+      permit: None,
+      viewing_key: vk("alice"),
+      id,
+    });
+    exec(&mut deps, "alice", msg).unwrap();
+    assert_eq!(get(&deps, "carol", "alice", "k").unwrap_err(), not_reader);
+  }
+
+  #[test]
+  fn groups_of_others_grant_no_access() {
+    let mut deps = setup();
+    let carols = create_group(&mut deps, "carol", &["bob"]);
+    let authz = msg::Authz::new("", false).update_reader_group(carols, true);
+    assert_eq!(
+      store(&mut deps, "alice", "k", authz).unwrap_err(),
+      ContractError::Unauthorized("not a owner".into())
+    );
+    let authz = msg::Authz::new("", false).update_reader_group(carols + 1, true);
+    assert_eq!(
+      store(&mut deps, "alice", "k", authz).unwrap_err(),
+      ContractError::NotFound("group".into())
+    );
+
+    let alices = create_group(&mut deps, "alice", &["bob"]);
+    store(&mut deps, "alice", "k", msg::Authz::new("", false)).unwrap();
+    let authz = msg::Authz::new("", false).update_reader_group(carols, true);
+    assert_eq!(
+      update_authz(&mut deps, "alice", "k", authz).unwrap_err(),
+      ContractError::Unauthorized("not a owner".into())
+    );
+    let authz = msg::Authz::new("", false).update_reader_group(alices, true);
+    update_authz(&mut deps, "alice", "k", authz).unwrap();
+    assert!(get(&deps, "bob", "alice", "k").unwrap().is_some());
+
+    // after a transfer the entry still refers the group of its former owner
+    transfer(&mut deps, "alice", "k", Some("carol")).unwrap();
+    accept(&mut deps, "carol", "alice", "k").unwrap();
+    assert_eq!(
+      get(&deps, "bob", "carol", "k").unwrap_err(),
+      ContractError::Unauthorized("not a reader".into())
+    );
+  }
+
+  fn create_viewing_key(deps: &mut MockDeps, account: &str) -> String {
+    let msg = msg::ExecuteMsg::CreateViewingKey(msg::ExecuteCreateViewingKey {
+      entropy: "more".to_string(),
+    });
+    let res = exec(deps, account, msg).unwrap();
+    match from_binary(&res.data.unwrap()).unwrap() {
+      msg::ExecuteAnswer::ViewingKey(key) => key,
+      a => panic!("unexpected answer {:?}", a),
+    }
+  }
+
+  #[test]
+  fn created_viewing_keys_replace_the_old_one_and_differ() {
+    let mut deps = setup();
+    store(&mut deps, "alice", "k", msg::Authz::new("", false)).unwrap();
+    let key = create_viewing_key(&mut deps, "alice");
+    assert!(key.starts_with(viewing_key::VIEWING_KEY_PREFIX));
+    assert!(viewing_key::check(&deps.storage, &Addr::unchecked("alice"), &key).unwrap());
+    assert_eq!(
+      get(&deps, "alice", "alice", "k").unwrap_err(),
+      ContractError::Unauthorized("wrong viewing key".into())
+    );
+
+    let again = create_viewing_key(&mut deps, "alice");
+    assert_ne!(again, key);
+    assert!(!viewing_key::check(&deps.storage, &Addr::unchecked("alice"), &key).unwrap());
+
+    let mut msg = instantiate_msg();
+    msg.entropy = "other".to_string();
+    let mut other = setup_with(msg);
+    let mut same = setup();
+    let first = create_viewing_key(&mut same, "alice");
+    assert_eq!(first, key);
+    assert_ne!(create_viewing_key(&mut other, "alice"), key);
+  }
+
+  /// signs SNIP-24 permits with a secp256k1 key, as a wallet does
+  struct PermitSigner(k256::ecdsa::SigningKey);
+
+  impl PermitSigner {
+    fn new(seed: u8) -> Self {
+      Self(k256::ecdsa::SigningKey::from_bytes(&[seed; 32]).unwrap())
+    }
+
+    fn pub_key(&self) -> Vec<u8> {
+      self.0.verifying_key().to_bytes().to_vec()
+    }
+
+    fn address(&self) -> String {
+      use bech32::ToBase32;
+      use ripemd160::Digest;
+      let hash = ripemd160::Ripemd160::digest(&secret_toolkit::crypto::sha_256(&self.pub_key()));
+      bech32::encode("secret", hash.to_base32(), bech32::Variant::Bech32).unwrap()
+    }
+
+    fn permit(&self, name: &str, token: &str, permissions: Vec<msg::Permissions>) -> msg::Permit {
+      use k256::ecdsa::signature::Signer;
+      use secret_toolkit::permit::{PermitParams, PermitSignature, PubKey};
+      let params = PermitParams {
+        allowed_tokens: vec![token.to_string()],
+        permit_name: name.to_string(),
+        chain_id: mock_env().block.chain_id,
+        permissions,
+      };
+      let signed = serde_json::json!({
+        "account_number": "0",
+        "chain_id": params.chain_id,
+        "fee": { "amount": [{ "amount": "0", "denom": "uscrt" }], "gas": "1" },
+        "memo": "",
+        "msgs": [{
+          "type": "query_permit",
+          "value": {
+            "allowed_tokens": params.allowed_tokens,
+            "permissions": params.permissions,
+            "permit_name": params.permit_name,
+          },
+        }],
+        "sequence": "0",
+      });
+      let signature: k256::ecdsa::Signature = self.0.sign(&serde_json::to_vec(&signed).unwrap());
+      msg::Permit {
+        params,
+        signature: PermitSignature {
+          pub_key: PubKey {
+            r#type: "tendermint/PubKeySecp256k1".to_string(),
+            value: Binary::from(self.pub_key()),
+          },
+          signature: Binary::from(signature.as_ref()),
+        },
+      }
+    }
+  }
+
+  fn application(address: &str) -> msg::ExecuteAddApplication {
+    msg::ExecuteAddApplication {
+      address: address.to_string(),
+      code_hash: format!("{}_hash", address),
+      version: "1".to_string(),
+      state: None,
+      scopes: None,
+    }
+  }
+
+  /// setup() with the application "app" registered
+  fn setup_with_app() -> MockDeps {
+    let mut msg = instantiate_msg();
+    msg.applications = Some(vec![application("app")]);
+    setup_with(msg)
+  }
+
+  fn store_with_permit(
+    deps: &mut MockDeps,
+    env: Env,
+    sender: &str,
+    permit: &msg::Permit,
+    key: &str,
+  ) -> Result<Response, ContractError> {
+    let msg = msg::ExecuteMsg::Store(msg::ExecuteStore {
+      permit: Some(permit.clone()),
+      viewing_key: None,
+      key: key.to_string(),
+      version: "1".to_string(),
+      data: vec![1],
+      authz: msg::Authz::new("", false),
+      expires_at: None,
+    });
+    execute(deps.as_mut(), env, mock_info(sender, &[]), msg)
+  }
+
+  fn get_with_permit(
+    deps: &MockDeps,
+    permit: &msg::Permit,
+    owner: Option<&str>,
+    key: &str,
+  ) -> Result<Option<msg::DataOutput>, ContractError> {
+    let msg = msg::QueryMsg::Get(msg::QueryGet {
+      permit: Some(permit.clone()),
+      viewing_key: None,
+      owner: owner.map(|s| s.to_string()),
+      key: key.to_string(),
+    });
+    match from_binary(&query(deps.as_ref(), mock_env(), msg)?)? {
+      msg::QueryAnswer::Data(d) => Ok(d),
+      a => panic!("unexpected answer {:?}", a),
+    }
+  }
+
+  #[test]
+  fn permits_are_limited_to_their_scopes_prefixes_and_expiration() {
+    let mut deps = setup_with_app();
+    let user = PermitSigner::new(1);
+    let sender = user.address();
+    let env = mock_env();
+
+    let read = user.permit("read", "app", vec![msg::Permissions::Read]);
+    assert_eq!(
+      store_with_permit(&mut deps, env.clone(), &sender, &read, "a").unwrap_err(),
+      ContractError::Unauthorized("permit has no Write scope".into())
+    );
+    let prefixed = user.permit(
+      "prefixed",
+      "app",
+      vec![
+        msg::Permissions::Write,
+        msg::Permissions::KeyPrefix("pre/".to_string()),
+      ],
+    );
+    store_with_permit(&mut deps, env.clone(), &sender, &prefixed, "pre/a").unwrap();
+    assert_eq!(
+      store_with_permit(&mut deps, env.clone(), &sender, &prefixed, "a").unwrap_err(),
+      ContractError::Unauthorized("key not allowed by permit".into())
+    );
+    assert!(get_with_permit(&deps, &read, None, "pre/a")
+      .unwrap()
+      .is_some());
+    assert_eq!(
+      get_with_permit(&deps, &prefixed, None, "pre/a").unwrap_err(),
+      ContractError::Unauthorized("permit has no Read scope".into())
+    );
+
+    let expiring = user.permit(
+      "expiring",
+      "app",
+      vec![
+        msg::Permissions::Access,
+        msg::Permissions::ExpiresAt(msg::Expiration::AtHeight(env.block.height + 1)),
+      ],
+    );
+    store_with_permit(&mut deps, env.clone(), &sender, &expiring, "b").unwrap();
+    let mut later = env.clone();
+    later.block.height += 1;
+    assert_eq!(
+      store_with_permit(&mut deps, later, &sender, &expiring, "c").unwrap_err(),
+      ContractError::Unauthorized("permit expired".into())
+    );
+
+    let elsewhere = user.permit("elsewhere", "other", vec![msg::Permissions::Access]);
+    assert_eq!(
+      store_with_permit(&mut deps, env.clone(), &sender, &elsewhere, "c").unwrap_err(),
+      ContractError::Unauthorized("no allowed address".into())
+    );
+    let mut forged = read;
+    forged.params.permissions = vec![msg::Permissions::Access];
+    let err = store_with_permit(&mut deps, env, &sender, &forged, "c").unwrap_err();
+    assert!(matches!(
+      err,
+      ContractError::Std(StdError::GenericErr { .. })
+    ));
+  }
+
+  fn revoke(deps: &mut MockDeps, sender: &str, permit: &msg::Permit, name: &str) {
+    let msg = msg::ExecuteMsg::RevokePermit(msg::ExecuteRevokePermit {
+      permit: Some(permit.clone()),
+      viewing_key: None,
+      permit_name: name.to_string(),
+    });
+    exec(deps, sender, msg).unwrap();
+  }
+
+  fn revoked(
+    deps: &MockDeps,
+    permit: &msg::Permit,
+    start_after: Option<&str>,
+    limit: u32,
+  ) -> Vec<String> {
+    let msg = msg::QueryMsg::RevokedPermits(msg::QueryRevokedPermits {
+      permit: Some(permit.clone()),
+      viewing_key: None,
+      start_after: start_after.map(|s| s.to_string()),
+      limit: Some(limit),
+    });
+    match from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap() {
+      msg::QueryAnswer::RevokedPermits(names) => names,
+      a => panic!("unexpected answer {:?}", a),
+    }
+  }
+
+  #[test]
+  fn revoked_permits_are_rejected_and_listed() {
+    let mut deps = setup_with_app();
+    let user = PermitSigner::new(1);
+    let other = PermitSigner::new(2);
+    let (sender, env) = (user.address(), mock_env());
+    let p1 = user.permit("p1", "app", vec![msg::Permissions::Access]);
+    let p2 = user.permit("p2", "app", vec![msg::Permissions::Access]);
+    let others_p1 = other.permit("p1", "app", vec![msg::Permissions::Access]);
+    store_with_permit(&mut deps, env.clone(), &sender, &p1, "a").unwrap();
+
+    revoke(&mut deps, &sender, &p2, "p1");
+    let err = store_with_permit(&mut deps, env.clone(), &sender, &p1, "b").unwrap_err();
+    assert!(matches!(
+      err,
+      ContractError::Std(StdError::GenericErr { .. })
+    ));
+    assert!(get_with_permit(&deps, &p1, None, "a").is_err());
+    assert!(get_with_permit(&deps, &p2, None, "a").unwrap().is_some());
+    store_with_permit(&mut deps, env, &other.address(), &others_p1, "a").unwrap();
+
+    revoke(&mut deps, &sender, &p2, "p3");
+    revoke(&mut deps, &sender, &p2, "p0");
+    assert_eq!(revoked(&deps, &p2, None, 2), vec!["p0", "p1"]);
+    assert_eq!(revoked(&deps, &p2, Some("p1"), 2), vec!["p3"]);
+    assert_eq!(revoked(&deps, &others_p1, None, 2), Vec::<String>::new());
+
+    // keys of an account named like the revoked permits of another
+    let crafted = format!("{}{}", defs::PREFIX_REVOKED_PERMIT, sender);
+    let ns: [&[u8]; 2] = [defs::INDEX_BUCKET_KEY, crafted.as_bytes()];
+    crate::index::insert(&mut deps.storage, &ns, "k").unwrap();
+    assert_eq!(revoked(&deps, &p2, None, 10), vec!["p0", "p1", "p3"]);
+  }
+
+  /// stores as the account, sent by the sender
+  fn relay_store(
+    deps: &mut MockDeps,
+    sender: &str,
+    account: &str,
+    key: &str,
+  ) -> Result<Response, ContractError> {
+    let msg = msg::ExecuteMsg::Store(msg::ExecuteStore {
+      permit: None,
+      viewing_key: vk(account),
+      key: key.to_string(),
+      version: "1".to_string(),
+      data: vec![1],
+      authz: msg::Authz::new("", false),
+      expires_at: None,
+    });
+    exec(deps, sender, msg)
+  }
+
+  fn setup_with_policy(caller_policy: msg::CallerPolicy) -> MockDeps {
+    let mut msg = instantiate_msg();
+    msg.applications = Some(vec![application("app")]);
+    msg.caller_policy = Some(caller_policy);
+    setup_with(msg)
+  }
+
+  #[test]
+  fn caller_policy_both_accepts_users_and_applications() {
+    let mut deps = setup_with_policy(msg::CallerPolicy::Both);
+    relay_store(&mut deps, "alice", "alice", "a").unwrap();
+    relay_store(&mut deps, "app", "alice", "b").unwrap();
+  }
+
+  #[test]
+  fn caller_policy_application_only_rejects_users() {
+    let mut deps = setup_with_policy(msg::CallerPolicy::ApplicationOnly);
+    assert_eq!(
+      relay_store(&mut deps, "alice", "alice", "a").unwrap_err(),
+      ContractError::Unauthorized("not allowed".into())
+    );
+    relay_store(&mut deps, "app", "alice", "a").unwrap();
+  }
+
+  #[test]
+  fn caller_policy_direct_only_rejects_any_relayer() {
+    let mut deps = setup_with_policy(msg::CallerPolicy::DirectOnly);
+    relay_store(&mut deps, "alice", "alice", "a").unwrap();
+    assert_eq!(
+      relay_store(&mut deps, "app", "alice", "b").unwrap_err(),
+      ContractError::Unauthorized("applications not allowed".into())
+    );
+    assert_eq!(
+      relay_store(&mut deps, "bob", "alice", "b").unwrap_err(),
+      ContractError::Unauthorized("not allowed".into())
+    );
+
+    let user = PermitSigner::new(1);
+    let permit = user.permit("p", "app", vec![msg::Permissions::Access]);
+    store_with_permit(&mut deps, mock_env(), &user.address(), &permit, "a").unwrap();
+    assert_eq!(
+      store_with_permit(&mut deps, mock_env(), "bob", &permit, "b").unwrap_err(),
+      ContractError::Unauthorized("not sent by the account".into())
+    );
+  }
+
+  fn set_app_state(deps: &mut MockDeps, state: msg::ApplicationState) {
+    let msg = msg::ExecuteMsg::UpdateApplication(msg::ExecuteUpdateApplication {
+      address: "app".to_string(),
+      code_hash: None,
+      version: None,
+      state: Some(state),
+      scopes: None,
+    });
+    exec(deps, "admin", msg).unwrap();
+  }
+
+  #[test]
+  fn application_states_limit_its_permits_and_executes() {
+    let mut deps = setup_with_app();
+    let user = PermitSigner::new(1);
+    let (sender, env) = (user.address(), mock_env());
+    let permit = user.permit("p", "app", vec![msg::Permissions::Access]);
+    store_with_permit(&mut deps, env.clone(), &sender, &permit, "a").unwrap();
+
+    set_app_state(&mut deps, msg::ApplicationState::ReadOnly);
+    assert!(get_with_permit(&deps, &permit, None, "a")
+      .unwrap()
+      .is_some());
+    assert_eq!(
+      store_with_permit(&mut deps, env.clone(), &sender, &permit, "b").unwrap_err(),
+      ContractError::Unauthorized("permit has no Write scope".into())
+    );
+    assert_eq!(
+      relay_store(&mut deps, "app", "alice", "b").unwrap_err(),
+      ContractError::Unauthorized("application is not active".into())
+    );
+
+    set_app_state(
+      &mut deps,
+      msg::ApplicationState::SunsetAt(env.block.height + 1),
+    );
+    store_with_permit(&mut deps, env.clone(), &sender, &permit, "b").unwrap();
+    let mut later = env.clone();
+    later.block.height += 1;
+    assert_eq!(
+      store_with_permit(&mut deps, later, &sender, &permit, "c").unwrap_err(),
+      ContractError::Unauthorized("no allowed address".into())
+    );
+
+    set_app_state(&mut deps, msg::ApplicationState::Disabled);
+    assert_eq!(
+      get_with_permit(&deps, &permit, None, "a").unwrap_err(),
+      ContractError::Unauthorized("no allowed address".into())
+    );
+  }
+
+  #[test]
+  fn configs_of_former_versions_load() {
+    use cosmwasm_std::Storage;
+    let mut deps = mock_dependencies();
+    let config = br#"{
+      "my_address": "contract",
+      "owner_address": "admin",
+      "applications_address": ["app"],
+      "applications": [{
+        "address": "app2",
+        "code_hash": "app2_hash",
+        "version": "1",
+        "added_at": "0",
+        "state": "active"
+      }]
+    }"#;
+    deps.storage.set(
+      &cosmwasm_storage::to_length_prefixed(defs::CONTRACT_CONFIG_KEY_B),
+      config,
+    );
+    let config = ContractConfig::load(&deps.storage).unwrap();
+    assert_eq!(config.applications_address, Vec::<Addr>::new());
+    assert_eq!(config.applications.len(), 2);
+    for app in config.applications.iter() {
+      assert_eq!(app.scopes, msg::ApplicationScope::all());
+      assert!(!app.verified);
+      assert!(config
+        .check_application(&app.address, &mock_env().block)
+        .is_ok());
+    }
+
+    let msg = msg::ExecuteMsg::SetViewingKey(msg::ExecuteSetViewingKey {
+      key: "key".to_string(),
+    });
+    exec(&mut deps, "alice", msg).unwrap();
+    relay_store(&mut deps, "app", "alice", "a").unwrap();
+  }
+
+  /// applications answer the ping, except "evil" which runs another code
+  fn mock_applications(deps: &mut MockDeps) {
+    use cosmwasm_std::{ContractResult, SystemError, SystemResult, WasmQuery};
+    deps.querier.update_wasm(|q| match q {
+      WasmQuery::Smart { contract_addr, .. } if contract_addr != "evil" => SystemResult::Ok(
+        ContractResult::Ok(to_binary(&msg::ApplicationQueryAnswer::Pong {}).unwrap()),
+      ),
+      _ => SystemResult::Err(SystemError::Unknown {}),
+    });
+  }
+
+  #[test]
+  fn only_verified_applications_of_pinned_code_hashes_are_trusted() {
+    let mut deps = setup_with_app();
+    mock_applications(&mut deps);
+    let user = PermitSigner::new(1);
+    let permit = user.permit("p", "app", vec![msg::Permissions::Access]);
+    let msg = msg::ExecuteMsg::PinCodeHash(msg::ExecutePinCodeHash {
+      code_hash: "app_hash".to_string(),
+    });
+    exec(&mut deps, "admin", msg).unwrap();
+
+    assert_eq!(
+      relay_store(&mut deps, "app", "alice", "a").unwrap_err(),
+      ContractError::Unauthorized("application is not trusted".into())
+    );
+    assert_eq!(
+      relay_store(&mut deps, "mallory", "alice", "a").unwrap_err(),
+      ContractError::Unauthorized("not allowed".into())
+    );
+    assert_eq!(
+      get_with_permit(&deps, &permit, None, "a").unwrap_err(),
+      ContractError::Unauthorized("no allowed address".into())
+    );
+
+    let msg = msg::ExecuteMsg::VerifyApplication(msg::ExecuteVerifyApplication {
+      address: "app".to_string(),
+    });
+    exec(&mut deps, "anyone", msg).unwrap();
+    relay_store(&mut deps, "app", "alice", "a").unwrap();
+    store_with_permit(&mut deps, mock_env(), &user.address(), &permit, "a").unwrap();
+
+    let msg = msg::ExecuteMsg::AddApplication(application("evil"));
+    assert_eq!(
+      exec(&mut deps, "admin", msg).unwrap_err(),
+      ContractError::Unauthorized("code hash is not pinned".into())
+    );
+    let msg = msg::ExecuteMsg::PinCodeHash(msg::ExecutePinCodeHash {
+      code_hash: "evil_hash".to_string(),
+    });
+    exec(&mut deps, "admin", msg).unwrap();
+    let msg = msg::ExecuteMsg::AddApplication(application("evil"));
+    assert_eq!(
+      exec(&mut deps, "admin", msg).unwrap_err(),
+      ContractError::Unauthorized("code hash mismatch".into())
+    );
+  }
+
+  #[test]
+  fn frozen_trust_list_cannot_change() {
+    let mut deps = setup_with_app();
+    let msg = msg::ExecuteMsg::FreezeTrustList(msg::ExecuteFreezeTrustList {});
+    assert_eq!(
+      exec(&mut deps, "admin", msg).unwrap_err(),
+      ContractError::InvalidRequest("no pinned code hashes".into())
+    );
+    let msg = msg::ExecuteMsg::PinCodeHash(msg::ExecutePinCodeHash {
+      code_hash: "app_hash".to_string(),
+    });
+    exec(&mut deps, "admin", msg).unwrap();
+    let msg = msg::ExecuteMsg::FreezeTrustList(msg::ExecuteFreezeTrustList {});
+    exec(&mut deps, "admin", msg).unwrap();
+
+    let frozen = ContractError::Unauthorized("trust list is frozen".into());
+    let msg = msg::ExecuteMsg::PinCodeHash(msg::ExecutePinCodeHash {
+      code_hash: "other_hash".to_string(),
+    });
+    assert_eq!(exec(&mut deps, "admin", msg).unwrap_err(), frozen);
+    let msg = msg::ExecuteMsg::UnpinCodeHash(msg::ExecuteUnpinCodeHash {
+      code_hash: "app_hash".to_string(),
+    });
+    assert_eq!(exec(&mut deps, "admin", msg).unwrap_err(), frozen);
+  }
+
+  fn set_consent(
+    deps: &mut MockDeps,
+    sender: &str,
+    permit: Option<&msg::Permit>,
+    applications: &[&str],
+    code_hashes: &[&str],
+  ) {
+    let msg = msg::ExecuteMsg::SetConsent(msg::ExecuteSetConsent {
+      permit: permit.cloned(),
+      viewing_key: permit.map_or_else(|| vk(sender), |_| None),
+      applications: applications.iter().map(|s| s.to_string()).collect(),
+      code_hashes: code_hashes.iter().map(|s| s.to_string()).collect(),
+    });
+    exec(deps, sender, msg).unwrap();
+  }
+
+  fn setup_with_apps() -> MockDeps {
+    let mut msg = instantiate_msg();
+    msg.applications = Some(vec![application("app"), application("app2")]);
+    setup_with(msg)
+  }
+
+  #[test]
+  fn relayers_need_consent_with_a_viewing_key() {
+    let mut deps = setup_with_apps();
+    let denied = ContractError::Unauthorized("application not approved".into());
+    relay_store(&mut deps, "app", "alice", "a").unwrap();
+
+    set_consent(&mut deps, "alice", None, &["app"], &[]);
+    assert_eq!(
+      relay_store(&mut deps, "app2", "alice", "b").unwrap_err(),
+      denied
+    );
+    relay_store(&mut deps, "app", "alice", "b").unwrap();
+
+    set_consent(&mut deps, "alice", None, &["carol"], &["app2_hash"]);
+    assert_eq!(
+      relay_store(&mut deps, "app", "alice", "c").unwrap_err(),
+      denied
+    );
+    // an unverified registration may claim any code hash
+    assert_eq!(
+      relay_store(&mut deps, "app2", "alice", "c").unwrap_err(),
+      denied
+    );
+    mock_applications(&mut deps);
+    let msg = msg::ExecuteMsg::PinCodeHash(msg::ExecutePinCodeHash {
+      code_hash: "app2_hash".to_string(),
+    });
+    exec(&mut deps, "admin", msg).unwrap();
+    let msg = msg::ExecuteMsg::VerifyApplication(msg::ExecuteVerifyApplication {
+      address: "app2".to_string(),
+    });
+    exec(&mut deps, "anyone", msg).unwrap();
+    relay_store(&mut deps, "app2", "alice", "c").unwrap();
+    relay_store(&mut deps, "alice", "alice", "d").unwrap();
+  }
+
+  #[test]
+  fn relayers_need_consent_with_a_permit() {
+    let mut deps = setup_with_apps();
+    let denied = ContractError::Unauthorized("application not approved".into());
+    let user = PermitSigner::new(1);
+    let (sender, env) = (user.address(), mock_env());
+    let for_app = user.permit("p", "app", vec![msg::Permissions::Access]);
+    let for_app2 = user.permit("p2", "app2", vec![msg::Permissions::Access]);
+
+    set_consent(&mut deps, &sender, Some(&for_app), &["app2"], &[]);
+    assert_eq!(
+      store_with_permit(&mut deps, env.clone(), &sender, &for_app, "a").unwrap_err(),
+      denied
+    );
+    assert_eq!(
+      store_with_permit(&mut deps, env.clone(), "app", &for_app2, "a").unwrap_err(),
+      denied
+    );
+    assert_eq!(
+      store_with_permit(&mut deps, env.clone(), "bob", &for_app2, "a").unwrap_err(),
+      denied
+    );
+    store_with_permit(&mut deps, env.clone(), "app2", &for_app2, "a").unwrap();
+    store_with_permit(&mut deps, env, &sender, &for_app2, "b").unwrap();
+  }
+
+  #[test]
+  fn applications_execute_only_within_their_scopes() {
+    let mut msg = instantiate_msg();
+    let mut app = application("app");
+    app.scopes = Some(vec![msg::ApplicationScope::Store]);
+    msg.applications = Some(vec![app]);
+    let mut deps = setup_with(msg);
+
+    relay_store(&mut deps, "app", "alice", "a").unwrap();
+    let msg = msg::ExecuteMsg::UpdateData(msg::ExecuteUpdateData {
+      permit: None,
+      viewing_key: vk("alice"),
+      owner: None,
+      key: "a".to_string(),
+      version: "1".to_string(),
+      data: vec![2],
+      expires_at: None,
+      expected_revision: None,
+    });
+    assert_eq!(
+      exec(&mut deps, "app", msg.clone()).unwrap_err(),
+      ContractError::Unauthorized("application has no Update scope".into())
+    );
+    exec(&mut deps, "alice", msg).unwrap();
+
+    let msg = msg::ExecuteMsg::Batch(msg::ExecuteBatch {
+      permit: None,
+      viewing_key: vk("alice"),
+      ops: vec![store_op("b"), delete_op("a")],
+    });
+    assert_eq!(
+      exec(&mut deps, "app", msg).unwrap_err(),
+      ContractError::Unauthorized("application has no Delete scope".into())
+    );
+    let msg = msg::ExecuteMsg::CreateGroup(msg::ExecuteCreateGroup {
+      permit: None,
+      viewing_key: vk("alice"),
+      name: "g".to_string(),
+      members: vec![],
+    });
+    assert_eq!(
+      exec(&mut deps, "app", msg).unwrap_err(),
+      ContractError::Unauthorized("application has no Manage scope".into())
+    );
+
+    let user = PermitSigner::new(1);
+    let permit = user.permit("p", "app", vec![msg::Permissions::Access]);
+    assert_eq!(get_with_permit(&deps, &permit, None, "a").unwrap(), None);
+  }
+
+  #[test]
+  fn error_codes_are_recovered_from_a_leading_prefix_only() {
+    let err = ContractError::NotFound("op 1: entry".into());
+    assert_eq!(
+      ContractError::from(StdError::generic_err(err.to_string())),
+      err
+    );
+    let err = ContractError::InvalidRequest("both permit and viewing key".into());
+    assert_eq!(ContractError::from_message(&err.to_string()), Some(err));
+    assert_eq!(
+      ContractError::from_message("Querier contract error: [not_found] x"),
+      Some(ContractError::NotFound("x".into()))
+    );
+
+    assert_eq!(ContractError::from_message("bad key [not_found] x"), None);
+    assert_eq!(ContractError::from_message("[no_such_code] x"), None);
+    assert_eq!(ContractError::from_message("[not_found]x"), None);
+    let e = || StdError::generic_err("address [x] y");
+    assert_eq!(ContractError::from(e()), ContractError::Std(e()));
+  }
+
+  #[test]
+  fn permit_and_viewing_key_together_are_rejected() {
+    let mut deps = setup_with_app();
+    let user = PermitSigner::new(1);
+    let msg = msg::ExecuteMsg::Store(msg::ExecuteStore {
+      permit: Some(user.permit("p", "app", vec![msg::Permissions::Access])),
+      viewing_key: vk("alice"),
+      key: "a".to_string(),
+      version: "1".to_string(),
+      data: vec![1],
+      authz: msg::Authz::new("", false),
+      expires_at: None,
+    });
+    assert_eq!(
+      exec(&mut deps, "alice", msg).unwrap_err(),
+      ContractError::InvalidRequest("both permit and viewing key".into())
+    );
+  }
+
+  /// raw answers of every query about a single entry
+  fn entry_answers(deps: &MockDeps, account: &str, owner: &str, key: &str) -> Vec<Binary> {
+    let owner = Some(owner.to_string());
+    let msgs = vec![
+      msg::QueryMsg::Get(msg::QueryGet {
+        permit: None,
+        viewing_key: vk(account),
+        owner: owner.clone(),
+        key: key.to_string(),
+      }),
+      msg::QueryMsg::GetRevision(msg::QueryGetRevision {
+        permit: None,
+        viewing_key: vk(account),
+        owner: owner.clone(),
+        key: key.to_string(),
+        revision: 1,
+      }),
+      msg::QueryMsg::ListRevisions(msg::QueryListRevisions {
+        permit: None,
+        viewing_key: vk(account),
+        owner: owner.clone(),
+        key: key.to_string(),
+      }),
+      msg::QueryMsg::GetMeta(msg::QueryGetMeta {
+        permit: None,
+        viewing_key: vk(account),
+        owner: owner.clone(),
+        key: key.to_string(),
+      }),
+      msg::QueryMsg::Exists(msg::QueryExists {
+        permit: None,
+        viewing_key: vk(account),
+        owner,
+        key: key.to_string(),
+      }),
+    ];
+    msgs
+      .into_iter()
+      .map(|m| query(deps.as_ref(), mock_env(), m).unwrap())
+      .collect()
+  }
+
+  #[test]
+  fn privacy_mode_makes_forbidden_entries_look_missing() {
+    let mut msg = instantiate_msg();
+    msg.privacy_mode = Some(true);
+    msg.history_depth = Some(2);
+    let mut deps = setup_with(msg);
+    let authz = msg::Authz::new("", false).update("bob", true);
+    store(&mut deps, "alice", "k", authz).unwrap();
+
+    let missing = entry_answers(&deps, "carol", "alice", "none");
+    assert_eq!(entry_answers(&deps, "carol", "alice", "k"), missing);
+    assert_ne!(entry_answers(&deps, "bob", "alice", "k"), missing);
+
+    let not_found = ContractError::NotFound("entry".into());
+    assert_eq!(
+      update(&mut deps, "carol", "alice", "none", None).unwrap_err(),
+      not_found
+    );
+    assert_eq!(
+      update(&mut deps, "carol", "alice", "k", None).unwrap_err(),
+      not_found
+    );
+    assert_eq!(
+      update(&mut deps, "bob", "alice", "k", None).unwrap_err(),
+      not_found
+    );
+    assert_eq!(
+      accept(&mut deps, "carol", "alice", "none").unwrap_err(),
+      not_found
+    );
+    assert_eq!(
+      accept(&mut deps, "carol", "alice", "k").unwrap_err(),
+      not_found
+    );
+
+    let msg = msg::ExecuteMsg::SetPrivacyMode(msg::ExecuteSetPrivacyMode {
+      privacy_mode: false,
+    });
+    exec(&mut deps, "admin", msg).unwrap();
+    assert_eq!(
+      update(&mut deps, "carol", "alice", "k", None).unwrap_err(),
+      ContractError::Unauthorized("not a writer".into())
+    );
+  }
+
+  fn meta(
+    deps: &MockDeps,
+    account: &str,
+    owner: &str,
+    key: &str,
+  ) -> Result<Option<msg::MetaOutput>, ContractError> {
+    let msg = msg::QueryMsg::GetMeta(msg::QueryGetMeta {
+      permit: None,
+      viewing_key: vk(account),
+      owner: Some(owner.to_string()),
+      key: key.to_string(),
+    });
+    match from_binary(&query(deps.as_ref(), mock_env(), msg)?)? {
+      msg::QueryAnswer::Meta(m) => Ok(m),
+      a => panic!("unexpected answer {:?}", a),
+    }
+  }
+
+  fn exists(deps: &MockDeps, account: &str, owner: &str, key: &str) -> Result<bool, ContractError> {
+    let msg = msg::QueryMsg::Exists(msg::QueryExists {
+      permit: None,
+      viewing_key: vk(account),
+      owner: Some(owner.to_string()),
+      key: key.to_string(),
+    });
+    match from_binary(&query(deps.as_ref(), mock_env(), msg)?)? {
+      msg::QueryAnswer::Exists(b) => Ok(b),
+      a => panic!("unexpected answer {:?}", a),
+    }
+  }
+
+  #[test]
+  fn meta_and_exists_answer_for_readable_entries() {
+    let mut deps = setup();
+    let authz = msg::Authz::new("", false).update("bob", true);
+    store(&mut deps, "alice", "k", authz).unwrap();
+    update(&mut deps, "alice", "alice", "k", None).unwrap();
+
+    let m = meta(&deps, "bob", "alice", "k").unwrap().unwrap();
+    assert_eq!(m.key, "k");
+    assert_eq!(m.owner, "alice");
+    assert_eq!(m.version, "1");
+    assert_eq!(m.size, 1);
+    assert_eq!(m.revision, 2);
+    assert_eq!(meta(&deps, "bob", "alice", "none").unwrap(), None);
+    assert!(exists(&deps, "bob", "alice", "k").unwrap());
+    assert!(!exists(&deps, "bob", "alice", "none").unwrap());
+
+    let not_reader = ContractError::Unauthorized("not a reader".into());
+    assert_eq!(meta(&deps, "carol", "alice", "k").unwrap_err(), not_reader);
+    assert_eq!(
+      exists(&deps, "carol", "alice", "k").unwrap_err(),
+      not_reader
+    );
+    assert!(!exists(&deps, "carol", "alice", "none").unwrap());
+  }
+
+  fn get_many(
+    deps: &MockDeps,
+    permit: Option<&msg::Permit>,
+    account: &str,
+    owner: &str,
+    keys: &[&str],
+  ) -> Result<Vec<msg::GetManyResult>, ContractError> {
+    let msg = msg::QueryMsg::GetMany(msg::QueryGetMany {
+      permit: permit.cloned(),
+      viewing_key: if permit.is_some() { None } else { vk(account) },
+      owner: Some(owner.to_string()),
+      keys: keys.iter().map(|k| k.to_string()).collect(),
+    });
+    match from_binary(&query(deps.as_ref(), mock_env(), msg)?)? {
+      msg::QueryAnswer::Many(m) => Ok(m),
+      a => panic!("unexpected answer {:?}", a),
+    }
+  }
+
+  fn found_keys(results: &[msg::GetManyResult]) -> Vec<String> {
+    results
+      .iter()
+      .map(|r| match r {
+        msg::GetManyResult::Found(d) => format!("found {}", d.key),
+        msg::GetManyResult::Missing { key } => format!("missing {}", key),
+        msg::GetManyResult::Forbidden { key } => format!("forbidden {}", key),
+      })
+      .collect()
+  }
+
+  #[test]
+  fn get_many_answers_each_key_in_order() {
+    let mut deps = setup();
+    let authz = msg::Authz::new("", false).update("bob", true);
+    store(&mut deps, "alice", "k", authz).unwrap();
+    store(&mut deps, "alice", "secret", msg::Authz::new("", false)).unwrap();
+
+    let results = get_many(&deps, None, "bob", "alice", &["secret", "k", "none"]).unwrap();
+    assert_eq!(
+      found_keys(&results),
+      vec!["forbidden secret", "found k", "missing none"]
+    );
+    match &results[1] {
+      msg::GetManyResult::Found(d) => assert_eq!(d.data, vec![1]),
+      r => panic!("unexpected result {:?}", r),
+    }
+
+    let keys = vec!["k"; defs::LIST_MAX_LIMIT as usize + 1];
+    assert_eq!(
+      get_many(&deps, None, "bob", "alice", &keys).unwrap_err(),
+      ContractError::QuotaExceeded(format!("more than {} keys", defs::LIST_MAX_LIMIT))
+    );
+    assert_eq!(
+      get_many(&deps, None, "bob", "alice", &keys[1..])
+        .unwrap()
+        .len(),
+      defs::LIST_MAX_LIMIT as usize
+    );
+  }
+
+  #[test]
+  fn get_many_hides_keys_outside_the_permit_prefixes() {
+    let user = PermitSigner::new(1);
+    let sender = user.address();
+    let access = user.permit("access", "app", vec![msg::Permissions::Access]);
+    let prefixed = user.permit(
+      "prefixed",
+      "app",
+      vec![
+        msg::Permissions::Read,
+        msg::Permissions::KeyPrefix("pre/".to_string()),
+      ],
+    );
+    for privacy_mode in [false, true] {
+      let mut msg = instantiate_msg();
+      msg.applications = Some(vec![application("app")]);
+      msg.privacy_mode = Some(privacy_mode);
+      let mut deps = setup_with(msg);
+      store_with_permit(&mut deps, mock_env(), &sender, &access, "pre/a").unwrap();
+      store_with_permit(&mut deps, mock_env(), &sender, &access, "b").unwrap();
+
+      let results = get_many(&deps, Some(&prefixed), "", &sender, &["pre/a", "b"]).unwrap();
+      let hidden = if privacy_mode {
+        "missing b"
+      } else {
+        "forbidden b"
+      };
+      assert_eq!(found_keys(&results), vec!["found pre/a", hidden]);
+      let results = get_many(&deps, Some(&access), "", &sender, &["pre/a", "b"]).unwrap();
+      assert_eq!(found_keys(&results), vec!["found pre/a", "found b"]);
+    }
+  }
+
+  fn writer(account: &str, sender: &str) -> Option<msg::Writer> {
+    Some(msg::Writer {
+      account: account.to_string(),
+      sender: sender.to_string(),
+    })
+  }
+
+  #[test]
+  fn entries_record_when_and_by_whom_they_were_written() {
+    let mut deps = setup_with_policy(msg::CallerPolicy::Both);
+    let created = Some(msg::BlockStamp::new(&mock_env().block));
+    let authz = msg::Authz::new("", false).update_writer("bob", true);
+    store(&mut deps, "alice", "k", authz).unwrap();
+    let data = get(&deps, "alice", "alice", "k").unwrap().unwrap();
+    assert_eq!(data.created_at, created);
+    assert_eq!(data.updated_at, created);
+    assert_eq!(data.updated_by, writer("alice", "alice"));
+
+    let mut env = mock_env();
+    env.block.height += 10;
+    env.block.time = env.block.time.plus_seconds(60);
+    let msg = msg::ExecuteMsg::UpdateData(msg::ExecuteUpdateData {
+      permit: None,
+      viewing_key: vk("bob"),
+      owner: Some("alice".to_string()),
+      key: "k".to_string(),
+      version: "2".to_string(),
+      data: vec![2],
+      expires_at: None,
+      expected_revision: None,
+    });
+    execute(deps.as_mut(), env.clone(), mock_info("bob", &[]), msg).unwrap();
+    let data = get(&deps, "alice", "alice", "k").unwrap().unwrap();
+    assert_eq!(data.created_at, created);
+    assert_eq!(data.updated_at, Some(msg::BlockStamp::new(&env.block)));
+    assert_eq!(data.updated_by, writer("bob", "bob"));
+
+    relay_store(&mut deps, "app", "alice", "relayed").unwrap();
+    let meta = meta(&deps, "alice", "alice", "relayed").unwrap().unwrap();
+    assert_eq!(meta.created_at, created);
+    assert_eq!(meta.updated_by, writer("alice", "app"));
+  }
+
+  /// seq, key and deleted of a change
+  type Change = (u64, String, bool);
+
+  fn changes_since(
+    deps: &MockDeps,
+    account: &str,
+    seq: u64,
+    limit: Option<u32>,
+  ) -> Result<(Vec<Change>, u64), ContractError> {
+    let msg = msg::QueryMsg::ChangesSince(msg::QueryChangesSince {
+      permit: None,
+      viewing_key: vk(account),
+      seq,
+      limit,
+    });
+    match from_binary(&query(deps.as_ref(), mock_env(), msg)?)? {
+      msg::QueryAnswer::Changes(c) => Ok((
+        c.changes
+          .into_iter()
+          .map(|c| (c.seq, c.key, c.deleted))
+          .collect(),
+        c.last_seq,
+      )),
+      a => panic!("unexpected answer {:?}", a),
+    }
+  }
+
+  fn change(seq: u64, key: &str, deleted: bool) -> Change {
+    (seq, key.to_string(), deleted)
+  }
+
+  #[test]
+  fn changes_since_pages_by_last_seq() {
+    let mut deps = setup();
+    for key in ["a", "b", "c"] {
+      store(&mut deps, "alice", key, msg::Authz::new("", false)).unwrap();
+    }
+    store(&mut deps, "bob", "x", msg::Authz::new("", false)).unwrap();
+
+    assert_eq!(
+      changes_since(&deps, "alice", 0, Some(2)).unwrap(),
+      (vec![change(1, "a", false), change(2, "b", false)], 2)
+    );
+    assert_eq!(
+      changes_since(&deps, "alice", 2, Some(2)).unwrap(),
+      (vec![change(3, "c", false)], 4)
+    );
+    assert_eq!(changes_since(&deps, "alice", 4, None).unwrap(), (vec![], 4));
+    assert_eq!(changes_since(&deps, "alice", 9, None).unwrap(), (vec![], 4));
+    assert_eq!(
+      changes_since(&deps, "bob", 0, None).unwrap(),
+      (vec![change(4, "x", false)], 4)
+    );
+  }
+
+  #[test]
+  fn changes_are_seen_by_who_can_read_the_entry_now() {
+    let mut deps = setup();
+    store(&mut deps, "alice", "k", msg::Authz::new("", false)).unwrap();
+    assert_eq!(changes_since(&deps, "bob", 0, None).unwrap(), (vec![], 1));
+
+    let authz = msg::Authz::new("", false).update("bob", true);
+    update_authz(&mut deps, "alice", "k", authz).unwrap();
+    assert_eq!(
+      changes_since(&deps, "bob", 0, None).unwrap(),
+      (vec![change(1, "k", false), change(2, "k", false)], 2)
+    );
+
+    delete(&mut deps, "alice", "k").unwrap();
+    assert_eq!(changes_since(&deps, "bob", 0, None).unwrap(), (vec![], 3));
+    assert_eq!(
+      changes_since(&deps, "alice", 0, None).unwrap(),
+      (
+        vec![
+          change(1, "k", false),
+          change(2, "k", false),
+          change(3, "k", true)
+        ],
+        3
+      )
+    );
+  }
+
+  #[test]
+  fn changes_older_than_the_retention_are_dropped() {
+    let mut deps = setup();
+    let alice = Addr::unchecked("alice");
+    for _ in 0..defs::CHANGES_RETENTION + 1 {
+      crate::changes::record(&mut deps.storage, &alice, "k", true).unwrap();
+    }
+    assert_eq!(crate::changes::load(&deps.storage, 1).unwrap(), None);
+    assert!(crate::changes::load(&deps.storage, 2).unwrap().is_some());
+    assert_eq!(
+      changes_since(&deps, "alice", 0, None).unwrap_err(),
+      ContractError::NotFound("changes after 0".into())
+    );
+    let (found, last_seq) = changes_since(&deps, "alice", 1, Some(1)).unwrap();
+    assert_eq!((found, last_seq), (vec![change(2, "k", true)], 2));
+  }
+
+  #[test]
+  fn viewing_keys_are_not_relayed_by_strangers() {
+    let mut deps = setup();
+    store(&mut deps, "alice", "k", msg::Authz::new("", false)).unwrap();
+    let not_allowed = ContractError::Unauthorized("not allowed".into());
+
+    let msg = msg::ExecuteMsg::Delete(msg::ExecuteDelete {
+      permit: None,
+      viewing_key: vk("alice"),
+      key: "k".to_string(),
+      expected_revision: None,
+    });
+    assert_eq!(exec(&mut deps, "mallory", msg).unwrap_err(), not_allowed);
+    let msg = msg::ExecuteMsg::TransferEntry(msg::ExecuteTransferEntry {
+      permit: None,
+      viewing_key: vk("alice"),
+      key: "k".to_string(),
+      new_owner: Some("mallory".to_string()),
+      expected_revision: None,
+    });
+    assert_eq!(exec(&mut deps, "mallory", msg).unwrap_err(), not_allowed);
+    assert_eq!(
+      relay_store(&mut deps, "mallory", "alice", "other").unwrap_err(),
+      not_allowed
+    );
+    let data = get(&deps, "alice", "alice", "k").unwrap().unwrap();
+    assert_eq!(data.pending_owner, None);
+  }
+
+  fn list(
+    deps: &MockDeps,
+    account: &str,
+    start_after: Option<&str>,
+    limit: Option<u32>,
+  ) -> (Vec<String>, Option<String>) {
+    let msg = msg::QueryMsg::List(msg::QueryList {
+      permit: None,
+      viewing_key: vk(account),
+      owner: Some("alice".to_string()),
+      start_after: start_after.map(|s| s.to_string()),
+      limit,
+      with_version: None,
+    });
+    match from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap() {
+      msg::QueryAnswer::List(page) => {
+        (page.entries.into_iter().map(|e| e.key).collect(), page.next)
+      }
+      a => panic!("unexpected answer {:?}", a),
+    }
+  }
+
+  #[test]
+  fn list_pages_through_readable_keys() {
+    let mut deps = setup();
+    for (key, public) in [
+      ("k1", true),
+      ("k2", false),
+      ("k3", true),
+      ("k4", false),
+      ("k5", true),
+    ] {
+      store(&mut deps, "alice", key, msg::Authz::new("", public)).unwrap();
+    }
+    assert_eq!(
+      list(&deps, "bob", None, Some(2)),
+      (vec!["k1".into(), "k3".into()], Some("k3".into()))
+    );
+    assert_eq!(
+      list(&deps, "bob", Some("k3"), Some(2)),
+      (vec!["k5".into()], None)
+    );
+    assert_eq!(list(&deps, "bob", Some("k5"), None), (vec![], None));
+    let (keys, next) = list(&deps, "alice", None, None);
+    assert_eq!((keys.len(), next), (5, None));
+  }
+
+  #[test]
+  fn list_clamps_the_limit_and_resumes_after_the_scan_bound() {
+    let mut deps = setup();
+    for i in 0..defs::LIST_MAX_SCAN {
+      let key = format!("a{:04}", i);
+      store(&mut deps, "alice", &key, msg::Authz::new("", false)).unwrap();
+    }
+    for i in 0..=defs::LIST_MAX_LIMIT {
+      let key = format!("p{:04}", i);
+      store(&mut deps, "alice", &key, msg::Authz::new("", true)).unwrap();
+    }
+
+    let last_private = format!("a{:04}", defs::LIST_MAX_SCAN - 1);
+    assert_eq!(
+      list(&deps, "bob", None, Some(1000)),
+      (vec![], Some(last_private.clone()))
+    );
+    let (keys, next) = list(&deps, "bob", Some(&last_private), Some(1000));
+    assert_eq!(keys.len(), defs::LIST_MAX_LIMIT as usize);
+    assert_eq!(next, keys.last().cloned());
+    let last_public = format!("p{:04}", defs::LIST_MAX_LIMIT);
+    assert_eq!(
+      list(&deps, "bob", next.as_deref(), Some(1000)),
+      (vec![last_public], None)
+    );
+  }
+
+  #[test]
+  fn ownership_moves_only_when_accepted_and_can_be_renounced() {
+    let mut deps = setup();
+    let not_owner = ContractError::Unauthorized("not a owner".into());
+    let not_pending = ContractError::Unauthorized("not a pending owner".into());
+    let propose = |owner: &str| {
+      msg::ExecuteMsg::ProposeOwner(msg::ExecuteProposeOwner {
+        owner: owner.to_string(),
+      })
+    };
+    let accept = || msg::ExecuteMsg::AcceptOwner(msg::ExecuteAcceptOwner {});
+    let set_privacy_mode =
+      || msg::ExecuteMsg::SetPrivacyMode(msg::ExecuteSetPrivacyMode { privacy_mode: true });
+
+    assert_eq!(
+      exec(&mut deps, "bob", propose("bob")).unwrap_err(),
+      not_owner
+    );
+    exec(&mut deps, "admin", propose("bob")).unwrap();
+    assert_eq!(exec(&mut deps, "carol", accept()).unwrap_err(), not_pending);
+    exec(&mut deps, "admin", set_privacy_mode()).unwrap();
+    exec(&mut deps, "bob", accept()).unwrap();
+    let config = ContractConfig::load(&deps.storage).unwrap();
+    assert_eq!(config.owner_address, Some(Addr::unchecked("bob")));
+    assert_eq!(config.pending_owner_address, None);
+    assert_eq!(
+      exec(&mut deps, "admin", set_privacy_mode()).unwrap_err(),
+      not_owner
+    );
+
+    exec(&mut deps, "bob", propose("carol")).unwrap();
+    let msg = msg::ExecuteMsg::RenounceOwnership(msg::ExecuteRenounceOwnership {});
+    assert_eq!(
+      exec(&mut deps, "carol", msg.clone()).unwrap_err(),
+      not_owner
+    );
+    exec(&mut deps, "bob", msg).unwrap();
+    let config = ContractConfig::load(&deps.storage).unwrap();
+    assert_eq!(config.owner_address, None);
+    assert_eq!(
+      exec(&mut deps, "bob", set_privacy_mode()).unwrap_err(),
+      not_owner
+    );
+    assert_eq!(
+      exec(&mut deps, "bob", propose("bob")).unwrap_err(),
+      not_owner
+    );
+    assert_eq!(exec(&mut deps, "carol", accept()).unwrap_err(), not_pending);
+  }
+
+  #[test]
+  fn delete_removes_an_owned_entry() {
+    let mut deps = setup();
+    let authz = msg::Authz::new("", false)
+      .update("bob", true)
+      .update_writer("bob", true);
+    store(&mut deps, "alice", "k", authz).unwrap();
+    let delete = |account: &str, expected_revision: Option<u64>| {
+      msg::ExecuteMsg::Delete(msg::ExecuteDelete {
+        permit: None,
+        viewing_key: vk(account),
+        key: "k".to_string(),
+        expected_revision,
+      })
+    };
+
+    assert_eq!(
+      exec(&mut deps, "alice", delete("alice", Some(2))).unwrap_err(),
+      ContractError::Conflict("expected revision 2 but 1".into())
+    );
+    let msg = msg::ExecuteMsg::Delete(msg::ExecuteDelete {
+      permit: None,
+      viewing_key: vk("bob"),
+      key: "k".to_string(),
+      expected_revision: None,
+    });
+    assert_eq!(
+      exec(&mut deps, "bob", msg).unwrap_err(),
+      ContractError::NotFound("entry".into())
+    );
+    let res = exec(&mut deps, "alice", delete("alice", Some(1))).unwrap();
+    assert_eq!(attr(&res, "seq"), "2");
+    assert_eq!(get(&deps, "alice", "alice", "k").unwrap(), None);
+    assert_eq!(
+      exec(&mut deps, "alice", delete("alice", None)).unwrap_err(),
+      ContractError::NotFound("entry".into())
+    );
+  }
 }
@@ -1,25 +1,172 @@
-use crate::defs;
-use cosmwasm_std::{Addr, StdError, StdResult, Storage};
+use crate::error::ContractError;
+use crate::{defs, msg};
+use cosmwasm_std::{Addr, BlockInfo, StdResult, Storage, Timestamp};
 use cosmwasm_storage::{singleton, singleton_read};
 use serde::{Deserialize, Serialize};
 
+#[derive(Serialize, Debug, Deserialize, Clone, PartialEq)]
+pub struct Application {
+  pub address: Addr,
+  pub code_hash: String,
+  pub version: String,
+  pub added_at: Timestamp,
+  pub state: msg::ApplicationState,
+  #[serde(default = "msg::ApplicationScope::all")]
+  pub scopes: Vec<msg::ApplicationScope>,
+  #[serde(default)]
+  pub verified: bool,
+}
+
+impl Application {
+  /// an application registered by address only, before code hashes and states.
+  /// active with every scope, as it was.
+  fn legacy(address: Addr) -> Self {
+    Self {
+      address,
+      code_hash: String::new(),
+      version: String::new(),
+      added_at: Timestamp::from_nanos(0),
+      state: msg::ApplicationState::Active,
+      scopes: msg::ApplicationScope::all(),
+      verified: false,
+    }
+  }
+  /// its permits are accepted.
+  pub fn is_enabled(&self, block: &BlockInfo) -> bool {
+    match self.state {
+      msg::ApplicationState::Active | msg::ApplicationState::ReadOnly => true,
+      msg::ApplicationState::SunsetAt(h) => block.height < h,
+      msg::ApplicationState::Disabled => false,
+    }
+  }
+  /// it can execute and its permits may write.
+  pub fn is_active(&self, block: &BlockInfo) -> bool {
+    match self.state {
+      msg::ApplicationState::Active => true,
+      msg::ApplicationState::SunsetAt(h) => block.height < h,
+      msg::ApplicationState::ReadOnly | msg::ApplicationState::Disabled => false,
+    }
+  }
+  pub fn check_scope(&self, scope: msg::ApplicationScope) -> Result<(), ContractError> {
+    if !self.scopes.contains(&scope) {
+      return Err(ContractError::Unauthorized(format!(
+        "application has no {:?} scope",
+        scope
+      )));
+    }
+    Ok(())
+  }
+  pub fn to_output(&self) -> msg::ApplicationOutput {
+    msg::ApplicationOutput {
+      address: self.address.to_string(),
+      code_hash: self.code_hash.clone(),
+      version: self.version.clone(),
+      added_at: self.added_at,
+      state: self.state.clone(),
+      scopes: self.scopes.clone(),
+      verified: self.verified,
+    }
+  }
+}
+
 #[derive(Serialize, Debug, Deserialize, Clone, PartialEq)]
 pub struct ContractConfig {
   pub my_address: Addr,
-  pub owner_address: Addr,
+  pub owner_address: Option<Addr>,
+  pub pending_owner_address: Option<Addr>,
+  /// applications of configs saved before the registry, moved into
+  /// `applications` on load.
+  #[serde(default, skip_serializing)]
   pub applications_address: Vec<Addr>,
+  #[serde(default)]
+  pub applications: Vec<Application>,
+  #[serde(default)]
+  pub history_depth: u32,
+  #[serde(default)]
+  pub caller_policy: msg::CallerPolicy,
+  /// when not empty, only verified applications of these code hashes are trusted.
+  #[serde(default)]
+  pub pinned_code_hashes: Vec<String>,
+  #[serde(default)]
+  pub trust_list_frozen: bool,
+  /// missing and forbidden entries look the same to callers.
+  #[serde(default)]
+  pub privacy_mode: bool,
+  /// query answers and response data are padded to a multiple of it. 0 disables padding.
+  #[serde(default = "default_padding_block_size")]
+  pub padding_block_size: u32,
+}
+
+fn default_padding_block_size() -> u32 {
+  defs::PADDING_BLOCK_SIZE_DEFAULT
 }
 
 impl ContractConfig {
-  pub fn check_owner(&self, addr: &Addr) -> StdResult<()> {
-    if &self.owner_address != addr {
-      return Err(StdError::generic_err(format!("not a owner")));
+  pub fn check_owner(&self, addr: &Addr) -> Result<(), ContractError> {
+    if self.owner_address.as_ref() != Some(addr) {
+      return Err(ContractError::Unauthorized("not a owner".into()));
     }
     Ok(())
   }
-  pub fn check_application(&self, addr: &Addr) -> StdResult<()> {
-    if !self.applications_address.contains(&addr) {
-      return Err(StdError::generic_err(format!("not allowed")));
+  pub fn check_pending_owner(&self, addr: &Addr) -> Result<(), ContractError> {
+    if self.pending_owner_address.as_ref() != Some(addr) {
+      return Err(ContractError::Unauthorized("not a pending owner".into()));
+    }
+    Ok(())
+  }
+  pub fn application(&self, addr: &Addr) -> Option<&Application> {
+    self.applications.iter().find(|a| &a.address == addr)
+  }
+  pub fn is_trusted(&self, app: &Application) -> bool {
+    self.pinned_code_hashes.is_empty()
+      || (app.verified && self.pinned_code_hashes.contains(&app.code_hash))
+  }
+  pub fn check_trust_list_not_frozen(&self) -> Result<(), ContractError> {
+    if self.trust_list_frozen {
+      return Err(ContractError::Unauthorized("trust list is frozen".into()));
+    }
+    Ok(())
+  }
+  pub fn check_application(&self, addr: &Addr, block: &BlockInfo) -> Result<(), ContractError> {
+    match self.application(addr) {
+      None => Err(ContractError::Unauthorized("not allowed".into())),
+      Some(a) if !self.is_trusted(a) => Err(ContractError::Unauthorized(
+        "application is not trusted".into(),
+      )),
+      Some(a) if !a.is_active(block) => Err(ContractError::Unauthorized(
+        "application is not active".into(),
+      )),
+      Some(_) => Ok(()),
+    }
+  }
+  /// registered applications must be active to execute, whatever the policy.
+  pub fn check_caller(&self, addr: &Addr, block: &BlockInfo) -> Result<(), ContractError> {
+    let registered = self.application(addr).is_some();
+    match self.caller_policy {
+      msg::CallerPolicy::DirectOnly if registered => Err(ContractError::Unauthorized(
+        "applications not allowed".into(),
+      )),
+      msg::CallerPolicy::ApplicationOnly => self.check_application(addr, block),
+      _ if registered => self.check_application(addr, block),
+      _ => Ok(()),
+    }
+  }
+  /// under DirectOnly the authenticated account must send the execute itself,
+  /// so that nobody relays it, registered or not.
+  pub fn check_sender(&self, sender: &Addr, account: &Addr) -> Result<(), ContractError> {
+    if self.caller_policy == msg::CallerPolicy::DirectOnly && sender != account {
+      return Err(ContractError::Unauthorized(
+        "not sent by the account".into(),
+      ));
+    }
+    Ok(())
+  }
+  pub fn check_history_depth(depth: u32) -> Result<(), ContractError> {
+    if defs::HISTORY_MAX_DEPTH < depth {
+      return Err(ContractError::QuotaExceeded(format!(
+        "history depth exceeds {}",
+        defs::HISTORY_MAX_DEPTH
+      )));
     }
     Ok(())
   }
@@ -28,6 +175,12 @@ impl ContractConfig {
     Ok(())
   }
   pub fn load(storage: &dyn Storage) -> StdResult<Self> {
-    singleton_read::<Self>(storage, defs::CONTRACT_CONFIG_KEY_B).load()
+    let mut config = singleton_read::<Self>(storage, defs::CONTRACT_CONFIG_KEY_B).load()?;
+    for address in std::mem::take(&mut config.applications_address) {
+      if config.application(&address).is_none() {
+        config.applications.push(Application::legacy(address));
+      }
+    }
+    Ok(config)
   }
 }
@@ -4,4 +4,33 @@ pub const CONTRACT_CONFIG_KEY_B: &[u8] = b"contract_config";
 pub const PREFIX_REVOKED_PERMIT: &str = "premit_revoke";
 pub const PREFIX_REVOKED_PERMIT_B: &[u8] = b"premit_revoke";
 
-pub const DATA_BUCKET_KEY: &[u8] = b"data";
+pub const DATA_BUCKET_KEY: &[u8] = b"owned_data";
+pub const LEGACY_DATA_BUCKET_KEY: &[u8] = b"data";
+pub const INDEX_BUCKET_KEY: &[u8] = b"index";
+pub const REVOKED_PERMIT_INDEX_KEY: &[u8] = b"revoked_permit_index";
+pub const HISTORY_BUCKET_KEY: &[u8] = b"history";
+pub const HISTORY_KEPT_BUCKET_KEY: &[u8] = b"history_kept";
+pub const REVISION_BUCKET_KEY: &[u8] = b"revision";
+pub const GROUP_BUCKET_KEY: &[u8] = b"group";
+pub const GROUP_COUNTER_KEY: &[u8] = b"group_counter";
+pub const VIEWING_KEY_BUCKET_KEY: &[u8] = b"viewing_key";
+pub const VIEWING_KEY_SEED_KEY: &[u8] = b"viewing_key_seed";
+pub const CONSENT_BUCKET_KEY: &[u8] = b"consent";
+pub const CHANGE_BUCKET_KEY: &[u8] = b"change";
+pub const CHANGE_SEQ_KEY: &[u8] = b"change_seq";
+
+pub const EXPIRY_HEIGHT_BUCKET_KEY: &[u8] = b"expiry_height";
+pub const EXPIRY_TIME_BUCKET_KEY: &[u8] = b"expiry_time";
+
+pub const LIST_DEFAULT_LIMIT: u32 = 30;
+pub const LIST_MAX_LIMIT: u32 = 100;
+// bounds the keys a list query looks at, readable or not
+pub const LIST_MAX_SCAN: u32 = 1000;
+// bounds the log entries a change feed query looks at, readable or not
+pub const CHANGES_MAX_SCAN: u64 = 1000;
+// bounds the change log, the older changes are dropped
+pub const CHANGES_RETENTION: u64 = 10000;
+
+pub const HISTORY_MAX_DEPTH: u32 = 32;
+
+pub const PADDING_BLOCK_SIZE_DEFAULT: u32 = 256;
@@ -0,0 +1,124 @@
+use cosmwasm_std::StdError;
+use thiserror::Error;
+
+/// every error but `Std` carries its code in the message as "[code] detail",
+/// so that it survives being returned to an application as a plain string.
+/// the application builds this file as its storage_error.rs.
+#[derive(Error, Debug, PartialEq)]
+pub enum ContractError {
+  #[error("{0}")]
+  Std(StdError),
+  #[error("[not_found] {0}")]
+  NotFound(String),
+  #[error("[unauthorized] {0}")]
+  Unauthorized(String),
+  #[error("[already_exists] {0}")]
+  AlreadyExists(String),
+  #[error("[conflict] {0}")]
+  Conflict(String),
+  #[error("[quota_exceeded] {0}")]
+  QuotaExceeded(String),
+  #[error("[unknown_format_version] {0}")]
+  UnknownFormatVersion(String),
+  #[error("[invalid_request] {0}")]
+  InvalidRequest(String),
+  #[error("[unexpected_response] {0}")]
+  UnexpectedResponse(String),
+}
+
+impl ContractError {
+  pub fn code(&self) -> Option<&'static str> {
+    match self {
+      ContractError::Std(_) => None,
+      ContractError::NotFound(_) => Some("not_found"),
+      ContractError::Unauthorized(_) => Some("unauthorized"),
+      ContractError::AlreadyExists(_) => Some("already_exists"),
+      ContractError::Conflict(_) => Some("conflict"),
+      ContractError::QuotaExceeded(_) => Some("quota_exceeded"),
+      ContractError::UnknownFormatVersion(_) => Some("unknown_format_version"),
+      ContractError::InvalidRequest(_) => Some("invalid_request"),
+      ContractError::UnexpectedResponse(_) => Some("unexpected_response"),
+    }
+  }
+
+  /// recovers the error from a message starting with "[code] ", as
+  /// returned by a failed query to the storage contract, once the
+  /// prefixes the query added on its way back are stripped.
+  pub fn from_message(msg: &str) -> Option<Self> {
+    let (code, detail) = strip_query_prefixes(msg)
+      .strip_prefix('[')?
+      .split_once("] ")?;
+    let detail = detail.to_string();
+    match code {
+      "not_found" => Some(ContractError::NotFound(detail)),
+      "unauthorized" => Some(ContractError::Unauthorized(detail)),
+      "already_exists" => Some(ContractError::AlreadyExists(detail)),
+      "conflict" => Some(ContractError::Conflict(detail)),
+      "quota_exceeded" => Some(ContractError::QuotaExceeded(detail)),
+      "unknown_format_version" => Some(ContractError::UnknownFormatVersion(detail)),
+      "invalid_request" => Some(ContractError::InvalidRequest(detail)),
+      "unexpected_response" => Some(ContractError::UnexpectedResponse(detail)),
+      _ => None,
+    }
+  }
+
+  /// prefixes the detail, keeping the code.
+  pub fn context(self, prefix: &str) -> Self {
+    match self {
+      ContractError::Std(StdError::GenericErr { msg, .. }) => {
+        ContractError::Std(StdError::generic_err(format!("{}: {}", prefix, msg)))
+      }
+      ContractError::Std(e) => ContractError::Std(e),
+      ContractError::NotFound(s) => ContractError::NotFound(format!("{}: {}", prefix, s)),
+      ContractError::Unauthorized(s) => ContractError::Unauthorized(format!("{}: {}", prefix, s)),
+      ContractError::AlreadyExists(s) => ContractError::AlreadyExists(format!("{}: {}", prefix, s)),
+      ContractError::Conflict(s) => ContractError::Conflict(format!("{}: {}", prefix, s)),
+      ContractError::QuotaExceeded(s) => ContractError::QuotaExceeded(format!("{}: {}", prefix, s)),
+      ContractError::UnknownFormatVersion(s) => {
+        ContractError::UnknownFormatVersion(format!("{}: {}", prefix, s))
+      }
+      ContractError::InvalidRequest(s) => {
+        ContractError::InvalidRequest(format!("{}: {}", prefix, s))
+      }
+      ContractError::UnexpectedResponse(s) => {
+        ContractError::UnexpectedResponse(format!("{}: {}", prefix, s))
+      }
+    }
+  }
+}
+
+/// prefixed by the querier to the error of the queried contract
+const QUERY_ERROR_PREFIXES: &[&str] = &["Generic error: ", "Querier contract error: "];
+
+fn strip_query_prefixes(mut msg: &str) -> &str {
+  loop {
+    if let Some(rest) = QUERY_ERROR_PREFIXES
+      .iter()
+      .find_map(|p| msg.strip_prefix(p))
+    {
+      msg = rest;
+      continue;
+    }
+    // added by secret-toolkit's Query::query
+    match msg
+      .strip_prefix("Error performing ")
+      .and_then(|m| m.split_once(" query: "))
+    {
+      Some((_, rest)) => msg = rest,
+      None => return msg,
+    }
+  }
+}
+
+/// a failed query to the storage contract arrives as a generic error;
+/// its code is recovered from the message.
+impl From<StdError> for ContractError {
+  fn from(e: StdError) -> Self {
+    match &e {
+      StdError::GenericErr { msg, .. } => {
+        ContractError::from_message(msg).unwrap_or(ContractError::Std(e))
+      }
+      _ => ContractError::Std(e),
+    }
+  }
+}
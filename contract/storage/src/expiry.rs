@@ -0,0 +1,106 @@
+use crate::defs;
+use crate::msg::Expiration;
+use cosmwasm_std::{Addr, BlockInfo, Order, StdError, StdResult, Storage, Timestamp};
+use cosmwasm_storage::to_length_prefixed;
+
+// entries having expiration, one storage entry each keyed by
+// "<expiration><owner>\0<key>" with the expiration in big endian, so that
+// purging reads the entries in the order they expire and stops at the first
+// one which has not.
+
+/// prefix and position of an expiration in the index. Never is not indexed.
+fn slot(expiry: &Expiration) -> Option<(Vec<u8>, u64)> {
+  match expiry {
+    Expiration::AtHeight(h) => Some((to_length_prefixed(defs::EXPIRY_HEIGHT_BUCKET_KEY), *h)),
+    Expiration::AtTime(t) => Some((to_length_prefixed(defs::EXPIRY_TIME_BUCKET_KEY), t.nanos())),
+    Expiration::Never => None,
+  }
+}
+
+fn index_key(expiry: &Expiration, owner: &Addr, key: &str) -> Option<Vec<u8>> {
+  let (prefix, position) = slot(expiry)?;
+  Some(
+    [
+      prefix.as_slice(),
+      &position.to_be_bytes(),
+      owner.as_bytes(),
+      &[0],
+      key.as_bytes(),
+    ]
+    .concat(),
+  )
+}
+
+pub fn insert(storage: &mut dyn Storage, expiry: &Expiration, owner: &Addr, key: &str) {
+  if let Some(k) = index_key(expiry, owner, key) {
+    storage.set(&k, &[1]);
+  }
+}
+
+pub fn remove(storage: &mut dyn Storage, expiry: &Expiration, owner: &Addr, key: &str) {
+  if let Some(k) = index_key(expiry, owner, key) {
+    storage.remove(&k);
+  }
+}
+
+/// moves an entry in the index from its old expiration to the new one
+pub fn update(
+  storage: &mut dyn Storage,
+  owner: &Addr,
+  key: &str,
+  old: Option<&Expiration>,
+  new: Option<&Expiration>,
+) {
+  if old == new {
+    return;
+  }
+  if let Some(e) = old {
+    remove(storage, e, owner, key);
+  }
+  if let Some(e) = new {
+    insert(storage, e, owner, key);
+  }
+}
+
+/// at most `limit` indexed entries expired at the block, the earliest first by kind
+pub fn expired(
+  storage: &dyn Storage,
+  block: &BlockInfo,
+  limit: usize,
+) -> StdResult<Vec<(Expiration, Addr, String)>> {
+  let bounds = [
+    Expiration::AtHeight(block.height),
+    Expiration::AtTime(block.time),
+  ];
+  let mut found = Vec::new();
+  for bound in bounds.iter() {
+    let (prefix, position) = match slot(bound) {
+      Some(s) => s,
+      None => continue,
+    };
+    let end = [prefix.as_slice(), &position.saturating_add(1).to_be_bytes()].concat();
+    for (k, _) in storage
+      .range(Some(&prefix), Some(&end), Order::Ascending)
+      .take(limit - found.len())
+    {
+      found.push(parse(bound, &k[prefix.len()..])?);
+    }
+  }
+  Ok(found)
+}
+
+fn parse(kind: &Expiration, k: &[u8]) -> StdResult<(Expiration, Addr, String)> {
+  let (pos, rest) = k.split_at(8);
+  let pos = u64::from_be_bytes(pos.try_into().unwrap());
+  let sep = rest
+    .iter()
+    .position(|b| *b == 0)
+    .ok_or_else(|| StdError::parse_err("expiry index", "no key separator"))?;
+  let owner = String::from_utf8(rest[..sep].to_vec())?;
+  let key = String::from_utf8(rest[sep + 1..].to_vec())?;
+  let expiry = match kind {
+    Expiration::AtHeight(_) => Expiration::AtHeight(pos),
+    _ => Expiration::AtTime(Timestamp::from_nanos(pos)),
+  };
+  Ok((expiry, Addr::unchecked(owner), key))
+}
@@ -0,0 +1,196 @@
+use crate::authn::{Authn, Scope};
+use crate::contract_config::ContractConfig;
+use crate::error::ContractError;
+use crate::{defs, msg};
+use cosmwasm_std::{Addr, Deps, DepsMut, Env, MessageInfo, Response, StdResult, Storage};
+use cosmwasm_storage::{bucket, bucket_read, singleton, singleton_read};
+use serde::{Deserialize, Serialize};
+
+// owner-managed set of accounts, referred from msg::Authz by id
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Group {
+  owner: Addr,
+  name: String,
+  members: Vec<String>,
+}
+
+impl Group {
+  pub fn is_member(&self, account: &str) -> bool {
+    self.members.iter().any(|a| a.as_str() == account)
+  }
+}
+
+pub fn load(storage: &dyn Storage, id: u64) -> StdResult<Option<Group>> {
+  bucket_read::<Group>(storage, defs::GROUP_BUCKET_KEY).may_load(&id.to_be_bytes())
+}
+
+/// whether the account is a member of any of the groups owned by the owner.
+/// groups of others are ignored, so that they cannot grant access to the entry.
+pub fn is_member_of(
+  storage: &dyn Storage,
+  owner: &str,
+  ids: &[u64],
+  account: &str,
+) -> StdResult<bool> {
+  for id in ids {
+    if let Some(g) = load(storage, *id)? {
+      if g.owner.as_str() == owner && g.is_member(account) {
+        return Ok(true);
+      }
+    }
+  }
+  Ok(false)
+}
+
+/// fails unless every group exists and is owned by the owner
+pub fn check_owned(storage: &dyn Storage, ids: &[u64], owner: &Addr) -> Result<(), ContractError> {
+  for id in ids {
+    load_owned(storage, *id, owner)?;
+  }
+  Ok(())
+}
+
+fn load_owned(storage: &dyn Storage, id: u64, owner: &Addr) -> Result<Group, ContractError> {
+  match load(storage, id)? {
+    Some(g) if &g.owner == owner => Ok(g),
+    Some(_) => Err(ContractError::Unauthorized("not a owner".into())),
+    None => Err(ContractError::NotFound("group".into())),
+  }
+}
+
+fn save(storage: &mut dyn Storage, id: u64, group: &Group) -> StdResult<()> {
+  bucket::<Group>(storage, defs::GROUP_BUCKET_KEY).save(&id.to_be_bytes(), group)
+}
+
+fn next_id(storage: &mut dyn Storage) -> StdResult<u64> {
+  let id = singleton_read::<u64>(storage, defs::GROUP_COUNTER_KEY)
+    .may_load()?
+    .unwrap_or(0)
+    + 1;
+  singleton::<u64>(storage, defs::GROUP_COUNTER_KEY).save(&id)?;
+  Ok(id)
+}
+
+fn validate_members(deps: Deps, members: Vec<String>) -> StdResult<Vec<String>> {
+  members
+    .iter()
+    .map(|s| deps.api.addr_validate(s.as_str()).map(|a| a.to_string()))
+    .collect()
+}
+
+pub fn create(
+  deps: DepsMut,
+  _env: Env,
+  _info: MessageInfo,
+  _config: &ContractConfig,
+  authn: Option<Authn>,
+  msg: msg::ExecuteCreateGroup,
+) -> Result<Response, ContractError> {
+  if authn.is_none() {
+    return Err(ContractError::Unauthorized(
+      "no permit or viewing key".into(),
+    ));
+  }
+  let authn = authn.unwrap();
+  authn.check(Scope::Admin, None)?;
+  let mut members = validate_members(deps.as_ref(), msg.members)?;
+  members.sort();
+  members.dedup();
+  let group = Group {
+    owner: authn.account,
+    name: msg.name,
+    members,
+  };
+  let id = next_id(deps.storage)?;
+  save(deps.storage, id, &group)?;
+  Ok(Response::new().add_attribute("group_id", id.to_string()))
+}
+
+pub fn add_member(
+  deps: DepsMut,
+  _env: Env,
+  _info: MessageInfo,
+  _config: &ContractConfig,
+  authn: Option<Authn>,
+  msg: msg::ExecuteAddGroupMember,
+) -> Result<Response, ContractError> {
+  if authn.is_none() {
+    return Err(ContractError::Unauthorized(
+      "no permit or viewing key".into(),
+    ));
+  }
+  let authn = authn.unwrap();
+  authn.check(Scope::Admin, None)?;
+  let mut group = load_owned(deps.storage, msg.id, &authn.account)?;
+  let member = deps.api.addr_validate(msg.member.as_str())?.to_string();
+  if !group.is_member(member.as_str()) {
+    group.members.push(member);
+    save(deps.storage, msg.id, &group)?;
+  }
+  Ok(Response::new())
+}
+
+pub fn remove_member(
+  deps: DepsMut,
+  _env: Env,
+  _info: MessageInfo,
+  _config: &ContractConfig,
+  authn: Option<Authn>,
+  msg: msg::ExecuteRemoveGroupMember,
+) -> Result<Response, ContractError> {
+  if authn.is_none() {
+    return Err(ContractError::Unauthorized(
+      "no permit or viewing key".into(),
+    ));
+  }
+  let authn = authn.unwrap();
+  authn.check(Scope::Admin, None)?;
+  let mut group = load_owned(deps.storage, msg.id, &authn.account)?;
+  group.members.retain(|a| a.as_str() != msg.member.as_str());
+  save(deps.storage, msg.id, &group)?;
+  Ok(Response::new())
+}
+
+/// entries referring a deleted group simply lose its members' access
+pub fn delete(
+  deps: DepsMut,
+  _env: Env,
+  _info: MessageInfo,
+  _config: &ContractConfig,
+  authn: Option<Authn>,
+  msg: msg::ExecuteDeleteGroup,
+) -> Result<Response, ContractError> {
+  if authn.is_none() {
+    return Err(ContractError::Unauthorized(
+      "no permit or viewing key".into(),
+    ));
+  }
+  let authn = authn.unwrap();
+  authn.check(Scope::Admin, None)?;
+  load_owned(deps.storage, msg.id, &authn.account)?;
+  bucket::<Group>(deps.storage, defs::GROUP_BUCKET_KEY).remove(&msg.id.to_be_bytes());
+  Ok(Response::new())
+}
+
+pub fn get(
+  deps: Deps,
+  _env: Env,
+  _config: &ContractConfig,
+  authn: Option<Authn>,
+  msg: msg::QueryGetGroup,
+) -> Result<msg::QueryAnswer, ContractError> {
+  if authn.is_none() {
+    return Err(ContractError::Unauthorized(
+      "no permit or viewing key".into(),
+    ));
+  }
+  let authn = authn.unwrap();
+  authn.check(Scope::Read, None)?;
+  let group = load_owned(deps.storage, msg.id, &authn.account)?;
+  Ok(msg::QueryAnswer::Group(msg::GroupOutput {
+    id: msg.id,
+    name: group.name,
+    members: group.members,
+  }))
+}
@@ -0,0 +1,127 @@
+use crate::defs;
+use cosmwasm_std::{Addr, StdResult, Storage, Timestamp};
+use cosmwasm_storage::{Bucket, ReadonlyBucket};
+use serde::{Deserialize, Serialize};
+
+// the revisions kept for an entry are listed, oldest first, so that evicting and
+// clearing them never misses one, even after the depth is lowered.
+// only the last `depth` of them can be read.
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Revision {
+  pub revision: u64,
+  pub version: String,
+  pub data: Vec<u8>,
+  pub time: Timestamp,
+  pub writer: Addr,
+}
+
+fn bucket_reader<'a>(
+  storage: &'a dyn Storage,
+  owner: &Addr,
+  key: &str,
+) -> ReadonlyBucket<'a, Revision> {
+  ReadonlyBucket::multilevel(
+    storage,
+    &[defs::HISTORY_BUCKET_KEY, owner.as_bytes(), key.as_bytes()],
+  )
+}
+fn bucket_writer<'a>(
+  storage: &'a mut dyn Storage,
+  owner: &Addr,
+  key: &str,
+) -> Bucket<'a, Revision> {
+  Bucket::multilevel(
+    storage,
+    &[defs::HISTORY_BUCKET_KEY, owner.as_bytes(), key.as_bytes()],
+  )
+}
+
+fn load_kept(storage: &dyn Storage, owner: &Addr, key: &str) -> StdResult<Vec<u64>> {
+  Ok(
+    ReadonlyBucket::<Vec<u64>>::multilevel(
+      storage,
+      &[defs::HISTORY_KEPT_BUCKET_KEY, owner.as_bytes()],
+    )
+    .may_load(key.as_bytes())?
+    .unwrap_or_default(),
+  )
+}
+
+fn save_kept(storage: &mut dyn Storage, owner: &Addr, key: &str, kept: &[u64]) -> StdResult<()> {
+  let mut bkt =
+    Bucket::<Vec<u64>>::multilevel(storage, &[defs::HISTORY_KEPT_BUCKET_KEY, owner.as_bytes()]);
+  if kept.is_empty() {
+    bkt.remove(key.as_bytes());
+    Ok(())
+  } else {
+    bkt.save(key.as_bytes(), &kept.to_vec())
+  }
+}
+
+/// the kept revisions which can be read, oldest first
+fn readable(kept: &[u64], depth: u32) -> &[u64] {
+  &kept[kept.len().saturating_sub(depth as usize)..]
+}
+
+/// keeps the revision and evicts the ones beyond the depth
+pub fn push(
+  storage: &mut dyn Storage,
+  owner: &Addr,
+  key: &str,
+  depth: u32,
+  rev: &Revision,
+) -> StdResult<()> {
+  let mut kept = load_kept(storage, owner, key)?;
+  let mut bkt = bucket_writer(storage, owner, key);
+  if depth > 0 {
+    bkt.save(&rev.revision.to_be_bytes(), rev)?;
+    kept.push(rev.revision);
+  }
+  let evicted = kept.len().saturating_sub(depth as usize);
+  for r in kept.drain(..evicted) {
+    bkt.remove(&r.to_be_bytes());
+  }
+  save_kept(storage, owner, key, &kept)
+}
+
+pub fn load(
+  storage: &dyn Storage,
+  owner: &Addr,
+  key: &str,
+  depth: u32,
+  revision: u64,
+) -> StdResult<Option<Revision>> {
+  let kept = load_kept(storage, owner, key)?;
+  if !readable(&kept, depth).contains(&revision) {
+    return Ok(None);
+  }
+  bucket_reader(storage, owner, key).may_load(&revision.to_be_bytes())
+}
+
+/// readable revisions, newest first
+pub fn list(
+  storage: &dyn Storage,
+  owner: &Addr,
+  key: &str,
+  depth: u32,
+) -> StdResult<Vec<Revision>> {
+  let kept = load_kept(storage, owner, key)?;
+  let bkt = bucket_reader(storage, owner, key);
+  let mut revs = Vec::new();
+  for r in readable(&kept, depth).iter().rev() {
+    if let Some(rev) = bkt.may_load(&r.to_be_bytes())? {
+      revs.push(rev);
+    }
+  }
+  Ok(revs)
+}
+
+pub fn clear(storage: &mut dyn Storage, owner: &Addr, key: &str) -> StdResult<()> {
+  let kept = load_kept(storage, owner, key)?;
+  let mut bkt = bucket_writer(storage, owner, key);
+  for r in kept {
+    bkt.remove(&r.to_be_bytes());
+  }
+  save_kept(storage, owner, key, &[])
+}
@@ -0,0 +1,52 @@
+use cosmwasm_std::{Order, StdResult, Storage};
+use cosmwasm_storage::to_length_prefixed_nested;
+
+// keys of a namespace, one storage entry each, so that listing a page reads
+// only the keys of the page. namespaces are nested like Bucket::multilevel,
+// e.g. [INDEX_BUCKET_KEY, owner].
+
+fn prefix(ns: &[&[u8]]) -> Vec<u8> {
+  to_length_prefixed_nested(ns)
+}
+
+/// the first key after every key starting with the prefix
+fn upper_bound(prefix: &[u8]) -> Vec<u8> {
+  let mut end = prefix.to_vec();
+  while let Some(b) = end.pop() {
+    if b < u8::MAX {
+      end.push(b + 1);
+      break;
+    }
+  }
+  end
+}
+
+pub fn insert(storage: &mut dyn Storage, ns: &[&[u8]], key: &str) -> StdResult<()> {
+  storage.set(&[prefix(ns).as_slice(), key.as_bytes()].concat(), &[1]);
+  Ok(())
+}
+
+pub fn remove(storage: &mut dyn Storage, ns: &[&[u8]], key: &str) -> StdResult<()> {
+  storage.remove(&[prefix(ns).as_slice(), key.as_bytes()].concat());
+  Ok(())
+}
+
+/// keys in ascending order, starting after `start_after` (which need not exist).
+/// keys are read as the iterator advances.
+pub fn keys_after<'a>(
+  storage: &'a dyn Storage,
+  ns: &[&[u8]],
+  start_after: Option<&str>,
+) -> Box<dyn Iterator<Item = StdResult<String>> + 'a> {
+  let prefix = prefix(ns);
+  let start = match start_after {
+    Some(s) => [prefix.as_slice(), s.as_bytes(), &[0]].concat(),
+    None => prefix.clone(),
+  };
+  let end = upper_bound(&prefix);
+  Box::new(
+    storage
+      .range(Some(&start), Some(&end), Order::Ascending)
+      .map(move |(k, _)| Ok(String::from_utf8(k[prefix.len()..].to_vec())?)),
+  )
+}
@@ -1,5 +1,14 @@
+pub mod authn;
+pub mod changes;
+pub mod consent;
 pub mod contract;
 pub mod contract_config;
 pub mod defs;
+pub mod error;
+pub mod expiry;
+pub mod group;
+pub mod history;
+pub mod index;
 pub mod msg;
 pub mod state;
+pub mod viewing_key;
@@ -1,22 +1,49 @@
-//use cosmwasm_std::Addr;
+use cosmwasm_std::{BlockInfo, Timestamp};
 use schemars::JsonSchema;
 use secret_toolkit::permit::Permit as Permit_;
 use secret_toolkit::utils::calls::{HandleCallback, InitCallback, Query};
 use serde::{Deserialize, Serialize};
 
+/// permissions signed in a permit. a permit needs a scope, Read, Write, Admin or Access.
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum Permissions {
+  /// all of Read, Write and Admin
   Access,
+  Read,
+  Write,
+  Admin,
+  /// the permit is rejected after this
+  ExpiresAt(Expiration),
+  /// restricts keys to the ones starting with any of the prefixes
+  KeyPrefix(String),
 }
 
 pub type Permit = Permit_<Permissions>;
 
+/// alternative to a permit for accounts which cannot sign one.
+/// queries only read. an execute must be sent by the account itself or by an
+/// enabled, trusted application.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ViewingKey {
+  pub address: String,
+  pub key: String,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Authz {
   owner: String,
   everyone_read: bool,
   readers: Vec<String>,
+  #[serde(default)]
+  everyone_write: bool,
+  #[serde(default)]
+  writers: Vec<String>,
+  /// ids of groups whose members can read. the groups must be owned by
+  /// the owner of the entry.
+  #[serde(default)]
+  reader_groups: Vec<u64>,
 }
 impl Authz {
   pub const KEY_EVERYONE: &'static str = "everyone";
@@ -27,12 +54,22 @@ impl Authz {
       owner: owner.to_string(),
       everyone_read: everyone_read,
       readers: vec![],
+      everyone_write: false,
+      writers: vec![],
+      reader_groups: vec![],
     }
   }
 
+  pub fn owner(&self) -> &str {
+    self.owner.as_str()
+  }
   pub fn is_owner(&self, account: &str) -> bool {
     self.owner.as_str() == account
   }
+  pub fn reader_groups(&self) -> &[u64] {
+    self.reader_groups.as_slice()
+  }
+  /// group membership is not resolved here, see reader_groups()
   pub fn is_readable(&self, account: &str) -> bool {
     if self.owner == account {
       return true;
@@ -40,11 +77,20 @@ impl Authz {
     if self.everyone_read {
       return true;
     }
-    self
-      .readers
-      .iter()
-      .find(|a| a.as_str() == account)
-      .is_some()
+    if self.readers.iter().any(|a| a.as_str() == account) {
+      return true;
+    }
+    self.is_writable(account)
+  }
+  /// writers can update data, but cannot change authz or delete
+  pub fn is_writable(&self, account: &str) -> bool {
+    if self.owner == account {
+      return true;
+    }
+    if self.everyone_write {
+      return true;
+    }
+    self.writers.iter().any(|a| a.as_str() == account)
   }
   pub fn update_owner(mut self, s: &str) -> Self {
     self.owner = s.to_string();
@@ -55,21 +101,63 @@ impl Authz {
     self
   }
   pub fn update(mut self, account: &str, b: bool) -> Self {
-    let find = self
-      .readers
+    Self::update_list(&mut self.readers, account, b);
+    self
+  }
+  pub fn update_everyone_write(mut self, b: bool) -> Self {
+    self.everyone_write = b;
+    self
+  }
+  pub fn update_writer(mut self, account: &str, b: bool) -> Self {
+    Self::update_list(&mut self.writers, account, b);
+    self
+  }
+  pub fn update_reader_group(mut self, id: u64, b: bool) -> Self {
+    self.reader_groups.retain(|g| *g != id);
+    if b {
+      self.reader_groups.push(id);
+    }
+    self
+  }
+  fn update_list(list: &mut Vec<String>, account: &str, b: bool) {
+    let find = list
       .iter()
       .enumerate()
       .find(|(_i, a)| a.as_str() == account);
     match (find, b) {
       (Some((i, _a)), false) => {
-        self.readers.remove(i);
+        list.remove(i);
       }
       (None, true) => {
-        self.readers.push(account.to_string());
+        list.push(account.to_string());
       }
       _ => (),
     }
-    self
+  }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Expiration {
+  AtHeight(u64),
+  AtTime(Timestamp),
+  /// clears the expiration of an entry
+  Never,
+}
+impl Expiration {
+  pub fn is_expired(&self, block: &BlockInfo) -> bool {
+    match self {
+      Expiration::AtHeight(h) => *h <= block.height,
+      Expiration::AtTime(t) => *t <= block.time,
+      Expiration::Never => false,
+    }
+  }
+  /// None for Never, as entries keep it
+  pub fn into_option(self) -> Option<Self> {
+    match self {
+      Expiration::Never => None,
+      e => Some(e),
+    }
   }
 }
 
@@ -80,10 +168,227 @@ pub struct DataOutput {
   pub version: String,
   pub data: Vec<u8>,
   pub authz: Authz,
+  pub revision: u64,
+  pub expires_at: Option<Expiration>,
+  pub pending_owner: Option<String>,
+  pub created_at: Option<BlockStamp>,
+  pub updated_at: Option<BlockStamp>,
+  pub updated_by: Option<Writer>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-pub struct InstantiateMsg {}
+#[serde(rename_all = "snake_case")]
+pub struct BlockStamp {
+  pub height: u64,
+  pub time: Timestamp,
+}
+impl BlockStamp {
+  pub fn new(block: &BlockInfo) -> Self {
+    Self {
+      height: block.height,
+      time: block.time,
+    }
+  }
+}
+
+/// who wrote an entry: the authenticated account and the sender of the
+/// execute, which is the application when relayed by one.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct Writer {
+  pub account: String,
+  pub sender: String,
+}
+
+/// result of a key of get_many. forbidden entries are missing in privacy mode.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum GetManyResult {
+  Found(Box<DataOutput>),
+  Missing { key: String },
+  Forbidden { key: String },
+}
+
+/// DataOutput without the data.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct MetaOutput {
+  pub key: String,
+  pub owner: String,
+  pub version: String,
+  /// length of the data in bytes
+  pub size: u64,
+  pub revision: u64,
+  pub expires_at: Option<Expiration>,
+  pub pending_owner: Option<String>,
+  pub created_at: Option<BlockStamp>,
+  pub updated_at: Option<BlockStamp>,
+  pub updated_by: Option<Writer>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ListOutput {
+  pub key: String,
+  pub version: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ListPageOutput {
+  pub entries: Vec<ListOutput>,
+  /// last key looked at. query again with it as start_after to continue;
+  /// None once the end of the list is reached.
+  pub next: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ChangeOutput {
+  pub seq: u64,
+  pub owner: String,
+  pub key: String,
+  pub deleted: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ChangesOutput {
+  pub changes: Vec<ChangeOutput>,
+  /// last sequence number looked at. query again from it to continue.
+  pub last_seq: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct RevisionOutput {
+  pub key: String,
+  pub revision: u64,
+  pub version: String,
+  /// omitted when listing revisions
+  pub data: Option<Vec<u8>>,
+  pub time: Timestamp,
+  pub writer: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct GroupOutput {
+  pub id: u64,
+  pub name: String,
+  pub members: Vec<String>,
+}
+
+/// lifecycle of a registered application.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ApplicationState {
+  Active,
+  /// its permits only allow reading and it cannot execute.
+  ReadOnly,
+  /// active until the block height, then disabled.
+  SunsetAt(u64),
+  Disabled,
+}
+
+/// what a registered application may execute. reading is always allowed.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ApplicationScope {
+  Store,
+  /// update_data and rollback
+  Update,
+  /// update_authz, transfer_entry and accept_entry
+  UpdateAuthz,
+  Delete,
+  /// groups, consent and permit revocation
+  Manage,
+}
+impl ApplicationScope {
+  pub fn all() -> Vec<Self> {
+    vec![
+      ApplicationScope::Store,
+      ApplicationScope::Update,
+      ApplicationScope::UpdateAuthz,
+      ApplicationScope::Delete,
+      ApplicationScope::Manage,
+    ]
+  }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ApplicationOutput {
+  pub address: String,
+  pub code_hash: String,
+  pub version: String,
+  pub added_at: Timestamp,
+  pub state: ApplicationState,
+  pub scopes: Vec<ApplicationScope>,
+  /// the code hash is pinned and the contract was proven to run it.
+  pub verified: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ConsentOutput {
+  pub applications: Vec<String>,
+  pub code_hashes: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct TrustListOutput {
+  pub code_hashes: Vec<String>,
+  pub frozen: bool,
+}
+
+/// the query every application answers so that its code hash can be verified.
+/// the query fails unless the contract runs the code hash it is sent with.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ApplicationQueryMsg {
+  Ping {},
+}
+impl Query for ApplicationQueryMsg {
+  const BLOCK_SIZE: usize = 256;
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ApplicationQueryAnswer {
+  Pong {},
+}
+
+/// who may send data executes to this contract.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum CallerPolicy {
+  /// only registered applications.
+  ApplicationOnly,
+  /// only users themselves: the sender must be the authenticated account.
+  DirectOnly,
+  #[default]
+  Both,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct InstantiateMsg {
+  /// initial owner. defaults to the instantiator.
+  pub owner: Option<String>,
+  pub applications: Option<Vec<ExecuteAddApplication>>,
+  /// number of revisions kept for each entry. 0 disables history.
+  pub history_depth: Option<u32>,
+  /// defaults to both.
+  pub caller_policy: Option<CallerPolicy>,
+  pub pinned_code_hashes: Option<Vec<String>>,
+  /// missing and forbidden entries look the same. defaults to false.
+  pub privacy_mode: Option<bool>,
+  /// query answers and response data are padded to a multiple of it. defaults to 256.
+  pub padding_block_size: Option<u32>,
+  /// seeds the generator of viewing keys, with the block and the instantiator.
+  pub entropy: String,
+}
 impl InitCallback for InstantiateMsg {
   const BLOCK_SIZE: usize = 256;
 }
@@ -92,13 +397,134 @@ impl InitCallback for InstantiateMsg {
 #[serde(rename_all = "snake_case")]
 pub struct QueryGet {
   pub permit: Option<Permit>,
+  pub viewing_key: Option<ViewingKey>,
+  /// owner of the entry. defaults to the authenticated account.
+  pub owner: Option<String>,
+  pub key: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct QueryGetMany {
+  pub permit: Option<Permit>,
+  pub viewing_key: Option<ViewingKey>,
+  /// owner of the entries. defaults to the authenticated account.
+  pub owner: Option<String>,
+  /// at most LIST_MAX_LIMIT keys
+  pub keys: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct QueryGetMeta {
+  pub permit: Option<Permit>,
+  pub viewing_key: Option<ViewingKey>,
+  /// owner of the entry. defaults to the authenticated account.
+  pub owner: Option<String>,
+  pub key: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct QueryExists {
+  pub permit: Option<Permit>,
+  pub viewing_key: Option<ViewingKey>,
+  /// owner of the entry. defaults to the authenticated account.
+  pub owner: Option<String>,
+  pub key: String,
+}
+
+/// keys the caller cannot read are skipped, so a page may hold fewer than `limit` keys.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct QueryList {
+  pub permit: Option<Permit>,
+  pub viewing_key: Option<ViewingKey>,
+  /// owner of the entries. defaults to the authenticated account.
+  pub owner: Option<String>,
+  pub start_after: Option<String>,
+  pub limit: Option<u32>,
+  pub with_version: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct QueryGetRevision {
+  pub permit: Option<Permit>,
+  pub viewing_key: Option<ViewingKey>,
+  pub owner: Option<String>,
   pub key: String,
+  pub revision: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct QueryListRevisions {
+  pub permit: Option<Permit>,
+  pub viewing_key: Option<ViewingKey>,
+  pub owner: Option<String>,
+  pub key: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct QueryChangesSince {
+  pub permit: Option<Permit>,
+  pub viewing_key: Option<ViewingKey>,
+  /// changes after this sequence number. 0 starts from the first change.
+  /// not_found when changes after it are no longer kept.
+  pub seq: u64,
+  pub limit: Option<u32>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct QueryGetGroup {
+  pub permit: Option<Permit>,
+  pub viewing_key: Option<ViewingKey>,
+  pub id: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct QueryRevokedPermits {
+  pub permit: Option<Permit>,
+  pub viewing_key: Option<ViewingKey>,
+  pub start_after: Option<String>,
+  pub limit: Option<u32>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct QueryListApplications {}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct QueryTrustList {}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct QueryConsent {
+  pub permit: Option<Permit>,
+  pub viewing_key: Option<ViewingKey>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
   Get(QueryGet),
+  GetMany(QueryGetMany),
+  GetMeta(QueryGetMeta),
+  Exists(QueryExists),
+  List(QueryList),
+  GetRevision(QueryGetRevision),
+  ListRevisions(QueryListRevisions),
+  ChangesSince(QueryChangesSince),
+  GetGroup(QueryGetGroup),
+  RevokedPermits(QueryRevokedPermits),
+  ListApplications(QueryListApplications),
+  TrustList(QueryTrustList),
+  Consent(QueryConsent),
 }
 impl Query for QueryMsg {
   const BLOCK_SIZE: usize = 256;
@@ -108,57 +534,350 @@ impl Query for QueryMsg {
 #[serde(rename_all = "snake_case")]
 pub enum QueryAnswer {
   Data(Option<DataOutput>),
+  Many(Vec<GetManyResult>),
+  Meta(Option<MetaOutput>),
+  Exists(bool),
+  List(ListPageOutput),
+  Revision(Option<RevisionOutput>),
+  Revisions(Vec<RevisionOutput>),
+  Changes(ChangesOutput),
+  Group(GroupOutput),
+  RevokedPermits(Vec<String>),
+  Applications(Vec<ApplicationOutput>),
+  TrustList(TrustListOutput),
+  Consent(ConsentOutput),
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
-pub struct ExecuteSetApplications {
-  pub applications: Vec<String>,
+pub struct ExecuteAddApplication {
+  pub address: String,
+  pub code_hash: String,
+  pub version: String,
+  /// defaults to active.
+  pub state: Option<ApplicationState>,
+  /// defaults to all scopes.
+  pub scopes: Option<Vec<ApplicationScope>>,
+}
+
+/// fields left empty are not changed.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ExecuteUpdateApplication {
+  pub address: String,
+  pub code_hash: Option<String>,
+  pub version: Option<String>,
+  pub state: Option<ApplicationState>,
+  pub scopes: Option<Vec<ApplicationScope>>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ExecuteRemoveApplication {
+  pub address: String,
+}
+
+/// anyone can ask to verify an application against the pinned code hashes.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ExecuteVerifyApplication {
+  pub address: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ExecutePinCodeHash {
+  pub code_hash: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ExecuteUnpinCodeHash {
+  pub code_hash: String,
+}
+
+/// makes the pinned code hashes unchangeable forever.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ExecuteFreezeTrustList {}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ExecuteProposeOwner {
+  pub owner: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ExecuteAcceptOwner {}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ExecuteRenounceOwnership {}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ExecuteSetCallerPolicy {
+  pub caller_policy: CallerPolicy,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ExecuteSetPrivacyMode {
+  pub privacy_mode: bool,
+}
+
+/// 0 disables padding.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ExecuteSetPaddingBlockSize {
+  pub padding_block_size: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ExecuteSetHistoryDepth {
+  pub history_depth: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ExecuteMigrateLegacy {
+  /// keys of the legacy entries to move, at most 100. the numbers moved, left
+  /// because the owner uses the key and left because the owner is not a valid
+  /// address are returned as `migrated`, `skipped` and `failed` attributes.
+  pub keys: Vec<String>,
+}
+
+/// removes at most `limit` expired entries, the earliest expired first.
+/// the number removed is returned as `purged` attribute.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ExecutePurgeExpired {
+  pub limit: Option<u32>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct ExecuteStore {
   pub permit: Option<Permit>,
+  pub viewing_key: Option<ViewingKey>,
   pub key: String,
   pub version: String,
   pub data: Vec<u8>,
   pub authz: Authz,
+  pub expires_at: Option<Expiration>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct ExecuteUpdateData {
   pub permit: Option<Permit>,
+  pub viewing_key: Option<ViewingKey>,
+  /// owner of the entry. defaults to the authenticated account.
+  pub owner: Option<String>,
   pub key: String,
   pub version: String,
   pub data: Vec<u8>,
+  /// replaces the expiration of the entry, Never clears it. None keeps it.
+  /// only the owner with admin scope may change it.
+  pub expires_at: Option<Expiration>,
+  /// fails with a conflict unless the entry is at this revision
+  pub expected_revision: Option<u64>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct ExecuteUpdateAuthz {
   pub permit: Option<Permit>,
+  pub viewing_key: Option<ViewingKey>,
   pub key: String,
+  /// owner in this authz is ignored. use TransferEntry to change it.
   pub authz: Authz,
+  /// fails with a conflict unless the entry is at this revision
+  pub expected_revision: Option<u64>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct ExecuteDelete {
   pub permit: Option<Permit>,
+  pub viewing_key: Option<ViewingKey>,
   pub key: String,
+  /// fails with a conflict unless the entry is at this revision
+  pub expected_revision: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ExecuteTransferEntry {
+  pub permit: Option<Permit>,
+  pub viewing_key: Option<ViewingKey>,
+  pub key: String,
+  /// None cancels the pending transfer
+  pub new_owner: Option<String>,
+  /// fails with a conflict unless the entry is at this revision
+  pub expected_revision: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ExecuteAcceptEntry {
+  pub permit: Option<Permit>,
+  pub viewing_key: Option<ViewingKey>,
+  /// current owner of the entry
+  pub owner: String,
+  pub key: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ExecuteRollback {
+  pub permit: Option<Permit>,
+  pub viewing_key: Option<ViewingKey>,
+  pub key: String,
+  pub revision: u64,
+  /// fails with a conflict unless the entry is at this revision
+  pub expected_revision: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ExecuteCreateGroup {
+  pub permit: Option<Permit>,
+  pub viewing_key: Option<ViewingKey>,
+  pub name: String,
+  pub members: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ExecuteAddGroupMember {
+  pub permit: Option<Permit>,
+  pub viewing_key: Option<ViewingKey>,
+  pub id: u64,
+  pub member: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ExecuteRemoveGroupMember {
+  pub permit: Option<Permit>,
+  pub viewing_key: Option<ViewingKey>,
+  pub id: u64,
+  pub member: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ExecuteDeleteGroup {
+  pub permit: Option<Permit>,
+  pub viewing_key: Option<ViewingKey>,
+  pub id: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ExecuteCreateViewingKey {
+  pub entropy: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ExecuteSetViewingKey {
+  pub key: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ExecuteRevokePermit {
+  pub permit: Option<Permit>,
+  pub viewing_key: Option<ViewingKey>,
+  pub permit_name: String,
+}
+
+/// replaces the applications allowed to relay the caller's operations,
+/// by address or, when registered and verified, by code hash. empty lists allow every relayer.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ExecuteSetConsent {
+  pub permit: Option<Permit>,
+  pub viewing_key: Option<ViewingKey>,
+  pub applications: Vec<String>,
+  pub code_hashes: Vec<String>,
+}
+
+/// an operation of a batch. permit and viewing key of each operation are ignored, the batch's ones are used.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Op {
+  Store(ExecuteStore),
+  UpdateData(ExecuteUpdateData),
+  UpdateAuthz(ExecuteUpdateAuthz),
+  Delete(ExecuteDelete),
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ExecuteBatch {
+  pub permit: Option<Permit>,
+  pub viewing_key: Option<ViewingKey>,
+  pub ops: Vec<Op>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
-  SetApplications(ExecuteSetApplications),
+  AddApplication(ExecuteAddApplication),
+  UpdateApplication(ExecuteUpdateApplication),
+  RemoveApplication(ExecuteRemoveApplication),
+  VerifyApplication(ExecuteVerifyApplication),
+  PinCodeHash(ExecutePinCodeHash),
+  UnpinCodeHash(ExecuteUnpinCodeHash),
+  FreezeTrustList(ExecuteFreezeTrustList),
+  ProposeOwner(ExecuteProposeOwner),
+  AcceptOwner(ExecuteAcceptOwner),
+  RenounceOwnership(ExecuteRenounceOwnership),
+  SetHistoryDepth(ExecuteSetHistoryDepth),
+  SetCallerPolicy(ExecuteSetCallerPolicy),
+  SetPrivacyMode(ExecuteSetPrivacyMode),
+  SetPaddingBlockSize(ExecuteSetPaddingBlockSize),
+  MigrateLegacy(ExecuteMigrateLegacy),
   Store(ExecuteStore),
   UpdateData(ExecuteUpdateData),
   UpdateAuthz(ExecuteUpdateAuthz),
   Delete(ExecuteDelete),
+  TransferEntry(ExecuteTransferEntry),
+  AcceptEntry(ExecuteAcceptEntry),
+  Rollback(ExecuteRollback),
+  PurgeExpired(ExecutePurgeExpired),
+  CreateGroup(ExecuteCreateGroup),
+  AddGroupMember(ExecuteAddGroupMember),
+  RemoveGroupMember(ExecuteRemoveGroupMember),
+  DeleteGroup(ExecuteDeleteGroup),
+  CreateViewingKey(ExecuteCreateViewingKey),
+  SetViewingKey(ExecuteSetViewingKey),
+  RevokePermit(ExecuteRevokePermit),
+  SetConsent(ExecuteSetConsent),
+  Batch(ExecuteBatch),
 }
 impl HandleCallback for ExecuteMsg {
   const BLOCK_SIZE: usize = 256;
 }
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct OpResult {
+  pub key: String,
+  /// revision of the entry after the op, none once deleted
+  pub revision: Option<u64>,
+  /// sequence number of the change
+  pub seq: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecuteAnswer {
+  Batch(Vec<OpResult>),
+  ViewingKey(String),
+}
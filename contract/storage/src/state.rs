@@ -1,6 +1,11 @@
-use crate::{defs, msg};
-use cosmwasm_std::{Addr, Deps, DepsMut, Env, MessageInfo, Response, StdError, StdResult, Storage};
-use cosmwasm_storage::{bucket, bucket_read, Bucket, ReadonlyBucket};
+use crate::authn::{Authn, Scope};
+use crate::contract_config::ContractConfig;
+use crate::error::ContractError;
+use crate::{changes, defs, expiry, group, history, index, msg};
+use cosmwasm_std::{
+  to_binary, Addr, Deps, DepsMut, Env, MessageInfo, Response, StdResult, Storage,
+};
+use cosmwasm_storage::{bucket, Bucket, ReadonlyBucket};
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
@@ -8,136 +13,1032 @@ pub struct Data {
   version: String,
   data: Vec<u8>,
   authz: msg::Authz,
+  #[serde(default)]
+  revision: u64,
+  #[serde(default)]
+  expires_at: Option<msg::Expiration>,
+  #[serde(default)]
+  pending_owner: Option<Addr>,
+  #[serde(default)]
+  created_at: Option<msg::BlockStamp>,
+  #[serde(default)]
+  updated_at: Option<msg::BlockStamp>,
+  #[serde(default)]
+  updated_by: Option<msg::Writer>,
 }
 
-fn bucket_reader<'a>(storage: &'a dyn Storage) -> ReadonlyBucket<'a, Data> {
-  bucket_read::<Data>(storage, defs::DATA_BUCKET_KEY)
+impl Data {
+  fn is_expired(&self, env: &Env) -> bool {
+    match &self.expires_at {
+      Some(e) => e.is_expired(&env.block),
+      None => false,
+    }
+  }
+  fn check_revision(&self, expected: Option<u64>) -> Result<(), ContractError> {
+    match expected {
+      Some(r) if r != self.revision => Err(ContractError::Conflict(format!(
+        "expected revision {} but {}",
+        r, self.revision
+      ))),
+      _ => Ok(()),
+    }
+  }
+  /// records the block and who wrote the entry last
+  fn touch(&mut self, env: &Env, info: &MessageInfo, account: &Addr) {
+    self.updated_at = Some(msg::BlockStamp::new(&env.block));
+    self.updated_by = Some(msg::Writer {
+      account: account.to_string(),
+      sender: info.sender.to_string(),
+    });
+  }
+  fn push_history(
+    &self,
+    storage: &mut dyn Storage,
+    env: &Env,
+    config: &ContractConfig,
+    owner: &Addr,
+    key: &str,
+    writer: &Addr,
+  ) -> StdResult<()> {
+    let rev = history::Revision {
+      revision: self.revision,
+      version: self.version.clone(),
+      data: self.data.clone(),
+      time: env.block.time,
+      writer: writer.clone(),
+    };
+    history::push(storage, owner, key, config.history_depth, &rev)
+  }
+}
+
+// entries are addressed by (owner, key)
+fn bucket_reader<'a>(storage: &'a dyn Storage, owner: &Addr) -> ReadonlyBucket<'a, Data> {
+  ReadonlyBucket::multilevel(storage, &[defs::DATA_BUCKET_KEY, owner.as_bytes()])
+}
+fn bucket_writer<'a>(storage: &'a mut dyn Storage, owner: &Addr) -> Bucket<'a, Data> {
+  Bucket::multilevel(storage, &[defs::DATA_BUCKET_KEY, owner.as_bytes()])
+}
+
+fn is_readable(storage: &dyn Storage, data: &Data, account: &Addr) -> StdResult<bool> {
+  if data.authz.is_readable(account.as_str()) {
+    return Ok(true);
+  }
+  group::is_member_of(
+    storage,
+    data.authz.owner(),
+    data.authz.reader_groups(),
+    account.as_str(),
+  )
+}
+
+/// a change is seen by who can read the entry now; one to an entry which is
+/// gone is seen by its owner only.
+fn is_change_readable(
+  storage: &dyn Storage,
+  env: &Env,
+  change: &changes::Change,
+  account: &Addr,
+) -> StdResult<bool> {
+  if &change.owner == account {
+    return Ok(true);
+  }
+  if change.deleted {
+    return Ok(false);
+  }
+  match bucket_reader(storage, &change.owner).may_load(change.key.as_bytes())? {
+    Some(data) if !data.is_expired(env) => is_readable(storage, &data, account),
+    _ => Ok(false),
+  }
+}
+
+/// in privacy mode an entry the caller may not access looks missing,
+/// so that keys of others cannot be probed.
+fn forbidden(config: &ContractConfig, reason: &str) -> ContractError {
+  if config.privacy_mode {
+    ContractError::NotFound("entry".into())
+  } else {
+    ContractError::Unauthorized(reason.into())
+  }
+}
+
+/// the last revision of a key, kept when its entry is removed so that
+/// a new entry at the key does not reuse revisions.
+fn last_revision(storage: &dyn Storage, owner: &Addr, key: &str) -> StdResult<u64> {
+  Ok(
+    ReadonlyBucket::<u64>::multilevel(storage, &[defs::REVISION_BUCKET_KEY, owner.as_bytes()])
+      .may_load(key.as_bytes())?
+      .unwrap_or(0),
+  )
+}
+
+fn remove_entry(storage: &mut dyn Storage, owner: &Addr, key: &str, data: &Data) -> StdResult<u64> {
+  bucket_writer(storage, owner).remove(key.as_bytes());
+  Bucket::<u64>::multilevel(storage, &[defs::REVISION_BUCKET_KEY, owner.as_bytes()])
+    .save(key.as_bytes(), &data.revision)?;
+  index::remove(storage, &[defs::INDEX_BUCKET_KEY, owner.as_bytes()], key)?;
+  if let Some(e) = &data.expires_at {
+    expiry::remove(storage, e, owner, key);
+  }
+  history::clear(storage, owner, key)?;
+  changes::record(storage, owner, key, true)
 }
-fn bucket_writer<'a>(storage: &'a mut dyn Storage) -> Bucket<'a, Data> {
-  bucket::<Data>(storage, defs::DATA_BUCKET_KEY)
+
+// entries stored before per-owner namespaces, addressed by key only
+fn legacy_bucket_writer<'a>(storage: &'a mut dyn Storage) -> Bucket<'a, Data> {
+  bucket::<Data>(storage, defs::LEGACY_DATA_BUCKET_KEY)
 }
 
 pub fn store(
   deps: DepsMut,
-  _env: Env,
-  _info: MessageInfo,
-  authn: Option<Addr>,
+  env: Env,
+  info: MessageInfo,
+  config: &ContractConfig,
+  authn: Option<Authn>,
   msg: msg::ExecuteStore,
-) -> StdResult<Response> {
+) -> Result<Response, ContractError> {
   if authn.is_none() {
-    return Err(StdError::generic_err("unauthorized"));
+    return Err(ContractError::Unauthorized(
+      "no permit or viewing key".into(),
+    ));
   }
-  let mut bkt = bucket_writer(deps.storage);
-
+  let authn = authn.unwrap();
+  authn.check(Scope::Write, Some(msg.key.as_str()))?;
+  let owner = authn.account;
+  group::check_owned(deps.storage, msg.authz.reader_groups(), &owner)?;
   let key = msg.key.as_bytes();
-  if let Some(mut _data) = bkt.may_load(key)? {
-    return Err(StdError::generic_err("alrady exists"));
-  } else {
-    let data = Data {
-      version: msg.version,
-      data: msg.data,
-      authz: msg.authz.update_owner(authn.unwrap().as_str()),
-    };
-    bkt.save(msg.key.as_bytes(), &data)?;
-    Ok(Response::new())
+  if let Some(old) = bucket_reader(deps.storage, &owner).may_load(key)? {
+    if !old.is_expired(&env) {
+      return Err(ContractError::AlreadyExists("entry".into()));
+    }
+    remove_entry(deps.storage, &owner, &msg.key, &old)?;
   }
+  let mut data = Data {
+    version: msg.version,
+    data: msg.data,
+    authz: msg.authz.update_owner(owner.as_str()),
+    revision: last_revision(deps.storage, &owner, &msg.key)? + 1,
+    expires_at: msg.expires_at.and_then(msg::Expiration::into_option),
+    pending_owner: None,
+    created_at: Some(msg::BlockStamp::new(&env.block)),
+    updated_at: None,
+    updated_by: None,
+  };
+  data.touch(&env, &info, &owner);
+  bucket_writer(deps.storage, &owner).save(key, &data)?;
+  index::insert(
+    deps.storage,
+    &[defs::INDEX_BUCKET_KEY, owner.as_bytes()],
+    msg.key.as_str(),
+  )?;
+  expiry::update(
+    deps.storage,
+    &owner,
+    &msg.key,
+    None,
+    data.expires_at.as_ref(),
+  );
+  data.push_history(deps.storage, &env, config, &owner, &msg.key, &owner)?;
+  let seq = changes::record(deps.storage, &owner, &msg.key, false)?;
+  Ok(
+    Response::new()
+      .add_attribute("revision", data.revision.to_string())
+      .add_attribute("seq", seq.to_string()),
+  )
 }
 
 pub fn delete(
   deps: DepsMut,
-  _env: Env,
+  env: Env,
   _info: MessageInfo,
-  authn: Option<Addr>,
+  config: &ContractConfig,
+  authn: Option<Authn>,
   msg: msg::ExecuteDelete,
-) -> StdResult<Response> {
+) -> Result<Response, ContractError> {
   if authn.is_none() {
-    return Err(StdError::generic_err("unauthorized"));
+    return Err(ContractError::Unauthorized(
+      "no permit or viewing key".into(),
+    ));
   }
-  let mut bkt = bucket_writer(deps.storage);
+  let authn = authn.unwrap();
+  authn.check(Scope::Admin, Some(msg.key.as_str()))?;
+  let owner = authn.account;
+  let bkt = bucket_reader(deps.storage, &owner);
   let key = msg.key.as_bytes();
-  if let Some(data) = bkt.may_load(key)? {
-    if !data.authz.is_owner(authn.unwrap().as_str()) {
-      return Err(StdError::generic_err("not a owner"));
+  if let Some(data) = bkt.may_load(key)?.filter(|d| !d.is_expired(&env)) {
+    if !data.authz.is_owner(owner.as_str()) {
+      return Err(forbidden(config, "not a owner"));
     }
-    bkt.remove(key);
+    data.check_revision(msg.expected_revision)?;
+    let seq = remove_entry(deps.storage, &owner, &msg.key, &data)?;
+    Ok(Response::new().add_attribute("seq", seq.to_string()))
   } else {
-    return Err(StdError::generic_err("not found"));
+    Err(ContractError::NotFound("entry".into()))
   }
-  Ok(Response::new())
 }
 
 pub fn update_data(
   deps: DepsMut,
-  _env: Env,
-  _info: MessageInfo,
-  authn: Option<Addr>,
+  env: Env,
+  info: MessageInfo,
+  config: &ContractConfig,
+  authn: Option<Authn>,
   msg: msg::ExecuteUpdateData,
-) -> StdResult<Response> {
+) -> Result<Response, ContractError> {
   if authn.is_none() {
-    return Err(StdError::generic_err("unauthorized"));
+    return Err(ContractError::Unauthorized(
+      "no permit or viewing key".into(),
+    ));
   }
-  let mut bkt = bucket_writer(deps.storage);
+  let authn = authn.unwrap();
+  authn.check(Scope::Write, Some(msg.key.as_str()))?;
+  let account = authn.account.clone();
+  let owner = match msg.owner {
+    Some(s) => deps.api.addr_validate(s.as_str())?,
+    None => account.clone(),
+  };
+  let mut bkt = bucket_writer(deps.storage, &owner);
   let key = msg.key.as_bytes();
-  if let Some(mut data) = bkt.may_load(key)? {
-    if !data.authz.is_owner(authn.unwrap().as_str()) {
-      return Err(StdError::generic_err("not a owner"));
+  if let Some(mut data) = bkt.may_load(key)?.filter(|d| !d.is_expired(&env)) {
+    if !data.authz.is_writable(account.as_str()) {
+      return Err(forbidden(config, "not a writer"));
     }
+    let expires_at = match msg.expires_at {
+      Some(e) => e.into_option(),
+      None => data.expires_at.clone(),
+    };
+    if expires_at != data.expires_at {
+      if !data.authz.is_owner(account.as_str()) {
+        return Err(forbidden(config, "not a owner"));
+      }
+      authn.check(Scope::Admin, Some(msg.key.as_str()))?;
+    }
+    data.check_revision(msg.expected_revision)?;
     data.version = msg.version;
     data.data = msg.data;
+    data.revision += 1;
+    let old_expiry = std::mem::replace(&mut data.expires_at, expires_at);
+    data.touch(&env, &info, &account);
     bkt.save(key, &data)?;
+    expiry::update(
+      deps.storage,
+      &owner,
+      &msg.key,
+      old_expiry.as_ref(),
+      data.expires_at.as_ref(),
+    );
+    data.push_history(deps.storage, &env, config, &owner, &msg.key, &account)?;
+    let seq = changes::record(deps.storage, &owner, &msg.key, false)?;
+    Ok(
+      Response::new()
+        .add_attribute("revision", data.revision.to_string())
+        .add_attribute("seq", seq.to_string()),
+    )
   } else {
-    return Err(StdError::generic_err("not found"));
+    Err(ContractError::NotFound("entry".into()))
   }
-  Ok(Response::new())
 }
 
 pub fn update_authz(
   deps: DepsMut,
-  _env: Env,
-  _info: MessageInfo,
-  authn: Option<Addr>,
+  env: Env,
+  info: MessageInfo,
+  config: &ContractConfig,
+  authn: Option<Authn>,
   msg: msg::ExecuteUpdateAuthz,
-) -> StdResult<Response> {
+) -> Result<Response, ContractError> {
+  if authn.is_none() {
+    return Err(ContractError::Unauthorized(
+      "no permit or viewing key".into(),
+    ));
+  }
+  let authn = authn.unwrap();
+  authn.check(Scope::Admin, Some(msg.key.as_str()))?;
+  let owner = authn.account;
+  group::check_owned(deps.storage, msg.authz.reader_groups(), &owner)?;
+  let mut bkt = bucket_writer(deps.storage, &owner);
+  let key = msg.key.as_bytes();
+  if let Some(mut data) = bkt.may_load(key)?.filter(|d| !d.is_expired(&env)) {
+    if !data.authz.is_owner(owner.as_str()) {
+      return Err(forbidden(config, "not a owner"));
+    }
+    data.check_revision(msg.expected_revision)?;
+    let owner_s = data.authz.owner().to_string();
+    data.authz = msg.authz.update_owner(owner_s.as_str());
+    data.revision += 1;
+    data.touch(&env, &info, &owner);
+    bkt.save(key, &data)?;
+    data.push_history(deps.storage, &env, config, &owner, &msg.key, &owner)?;
+    let seq = changes::record(deps.storage, &owner, &msg.key, false)?;
+    Ok(
+      Response::new()
+        .add_attribute("revision", data.revision.to_string())
+        .add_attribute("seq", seq.to_string()),
+    )
+  } else {
+    Err(ContractError::NotFound("entry".into()))
+  }
+}
+
+/// restores data and format version of a retained revision as a new revision
+pub fn rollback(
+  deps: DepsMut,
+  env: Env,
+  info: MessageInfo,
+  config: &ContractConfig,
+  authn: Option<Authn>,
+  msg: msg::ExecuteRollback,
+) -> Result<Response, ContractError> {
+  if authn.is_none() {
+    return Err(ContractError::Unauthorized(
+      "no permit or viewing key".into(),
+    ));
+  }
+  let authn = authn.unwrap();
+  authn.check(Scope::Write, Some(msg.key.as_str()))?;
+  let owner = authn.account;
+  let key = msg.key.as_bytes();
+  let bkt = bucket_reader(deps.storage, &owner);
+  if let Some(mut data) = bkt.may_load(key)?.filter(|d| !d.is_expired(&env)) {
+    if !data.authz.is_owner(owner.as_str()) {
+      return Err(forbidden(config, "not a owner"));
+    }
+    data.check_revision(msg.expected_revision)?;
+    let rev = history::load(
+      deps.storage,
+      &owner,
+      &msg.key,
+      config.history_depth,
+      msg.revision,
+    )?
+    .ok_or_else(|| ContractError::NotFound("revision".into()))?;
+    data.version = rev.version;
+    data.data = rev.data;
+    data.revision += 1;
+    data.touch(&env, &info, &owner);
+    bucket_writer(deps.storage, &owner).save(key, &data)?;
+    data.push_history(deps.storage, &env, config, &owner, &msg.key, &owner)?;
+    let seq = changes::record(deps.storage, &owner, &msg.key, false)?;
+    Ok(
+      Response::new()
+        .add_attribute("revision", data.revision.to_string())
+        .add_attribute("seq", seq.to_string()),
+    )
+  } else {
+    Err(ContractError::NotFound("entry".into()))
+  }
+}
+
+/// offers the entry to a new owner, who takes it over by accept_entry
+pub fn transfer_entry(
+  deps: DepsMut,
+  env: Env,
+  info: MessageInfo,
+  config: &ContractConfig,
+  authn: Option<Authn>,
+  msg: msg::ExecuteTransferEntry,
+) -> Result<Response, ContractError> {
   if authn.is_none() {
-    return Err(StdError::generic_err("unauthorized"));
+    return Err(ContractError::Unauthorized(
+      "no permit or viewing key".into(),
+    ));
   }
-  let mut bkt = bucket_writer(deps.storage);
+  let authn = authn.unwrap();
+  authn.check(Scope::Admin, Some(msg.key.as_str()))?;
+  let owner = authn.account;
+  let new_owner = match msg.new_owner {
+    Some(s) => Some(deps.api.addr_validate(s.as_str())?),
+    None => None,
+  };
+  let mut bkt = bucket_writer(deps.storage, &owner);
   let key = msg.key.as_bytes();
-  if let Some(mut data) = bkt.may_load(key)? {
-    if !data.authz.is_owner(authn.unwrap().as_str()) {
-      return Err(StdError::generic_err("not a owner"));
+  if let Some(mut data) = bkt.may_load(key)?.filter(|d| !d.is_expired(&env)) {
+    if !data.authz.is_owner(owner.as_str()) {
+      return Err(forbidden(config, "not a owner"));
     }
-    data.authz = msg.authz;
+    data.check_revision(msg.expected_revision)?;
+    data.pending_owner = new_owner;
+    data.touch(&env, &info, &owner);
     bkt.save(key, &data)?;
+    let seq = changes::record(deps.storage, &owner, &msg.key, false)?;
+    Ok(Response::new().add_attribute("seq", seq.to_string()))
   } else {
-    return Err(StdError::generic_err("not found"));
+    Err(ContractError::NotFound("entry".into()))
+  }
+}
+
+/// moves the entry offered by transfer_entry into the namespace of the new owner
+pub fn accept_entry(
+  deps: DepsMut,
+  env: Env,
+  info: MessageInfo,
+  config: &ContractConfig,
+  authn: Option<Authn>,
+  msg: msg::ExecuteAcceptEntry,
+) -> Result<Response, ContractError> {
+  if authn.is_none() {
+    return Err(ContractError::Unauthorized(
+      "no permit or viewing key".into(),
+    ));
+  }
+  let authn = authn.unwrap();
+  authn.check(Scope::Admin, Some(msg.key.as_str()))?;
+  let new_owner = authn.account;
+  let owner = deps.api.addr_validate(msg.owner.as_str())?;
+  let key = msg.key.as_bytes();
+  let data = bucket_reader(deps.storage, &owner)
+    .may_load(key)?
+    .filter(|d| !d.is_expired(&env));
+  if data.is_none() {
+    return Err(ContractError::NotFound("entry".into()));
+  }
+  let mut data = data.unwrap();
+  if data.pending_owner.as_ref() != Some(&new_owner) {
+    return Err(forbidden(config, "not a pending owner"));
+  }
+  if let Some(old) = bucket_reader(deps.storage, &new_owner).may_load(key)? {
+    if !old.is_expired(&env) {
+      return Err(ContractError::AlreadyExists("entry".into()));
+    }
+    remove_entry(deps.storage, &new_owner, &msg.key, &old)?;
+  }
+
+  let revs = history::list(deps.storage, &owner, &msg.key, config.history_depth)?;
+  remove_entry(deps.storage, &owner, &msg.key, &data)?;
+  for rev in revs.iter().rev() {
+    history::push(
+      deps.storage,
+      &new_owner,
+      &msg.key,
+      config.history_depth,
+      rev,
+    )?;
+  }
+
+  data.authz = data.authz.update_owner(new_owner.as_str());
+  data.pending_owner = None;
+  data.revision = data
+    .revision
+    .max(last_revision(deps.storage, &new_owner, &msg.key)?)
+    + 1;
+  data.touch(&env, &info, &new_owner);
+  bucket_writer(deps.storage, &new_owner).save(key, &data)?;
+  index::insert(
+    deps.storage,
+    &[defs::INDEX_BUCKET_KEY, new_owner.as_bytes()],
+    msg.key.as_str(),
+  )?;
+  expiry::update(
+    deps.storage,
+    &new_owner,
+    &msg.key,
+    None,
+    data.expires_at.as_ref(),
+  );
+  data.push_history(deps.storage, &env, config, &new_owner, &msg.key, &new_owner)?;
+  let seq = changes::record(deps.storage, &new_owner, &msg.key, false)?;
+  Ok(
+    Response::new()
+      .add_attribute("revision", data.revision.to_string())
+      .add_attribute("seq", seq.to_string()),
+  )
+}
+
+/// removes at most `limit` expired entries, the earliest expired first
+pub fn purge_expired(
+  deps: DepsMut,
+  env: Env,
+  _info: MessageInfo,
+  _config: &ContractConfig,
+  msg: msg::ExecutePurgeExpired,
+) -> Result<Response, ContractError> {
+  let limit = msg
+    .limit
+    .unwrap_or(defs::LIST_DEFAULT_LIMIT)
+    .min(defs::LIST_MAX_LIMIT) as usize;
+  let expired = expiry::expired(deps.storage, &env.block, limit)?;
+
+  let mut purged = 0;
+  for (e, owner, key) in expired.iter() {
+    match bucket_reader(deps.storage, owner).may_load(key.as_bytes())? {
+      Some(data) if data.expires_at.as_ref() == Some(e) => {
+        remove_entry(deps.storage, owner, key, &data)?;
+        purged += 1;
+      }
+      _ => expiry::remove(deps.storage, e, owner, key),
+    }
   }
-  Ok(Response::new())
+  Ok(Response::new().add_attribute("purged", purged.to_string()))
+}
+
+/// applies all operations or none of them, since an error reverts the whole execution.
+pub fn batch(
+  mut deps: DepsMut,
+  env: Env,
+  info: MessageInfo,
+  config: &ContractConfig,
+  authn: Option<Authn>,
+  msg: msg::ExecuteBatch,
+) -> Result<Response, ContractError> {
+  if authn.is_none() {
+    return Err(ContractError::Unauthorized(
+      "no permit or viewing key".into(),
+    ));
+  }
+  let mut results = Vec::with_capacity(msg.ops.len());
+  for (i, op) in msg.ops.into_iter().enumerate() {
+    let (key, r) = match op {
+      msg::Op::Store(m) => (
+        m.key.clone(),
+        store(
+          deps.branch(),
+          env.clone(),
+          info.clone(),
+          config,
+          authn.clone(),
+          m,
+        ),
+      ),
+      msg::Op::UpdateData(m) => (
+        m.key.clone(),
+        update_data(
+          deps.branch(),
+          env.clone(),
+          info.clone(),
+          config,
+          authn.clone(),
+          m,
+        ),
+      ),
+      msg::Op::UpdateAuthz(m) => (
+        m.key.clone(),
+        update_authz(
+          deps.branch(),
+          env.clone(),
+          info.clone(),
+          config,
+          authn.clone(),
+          m,
+        ),
+      ),
+      msg::Op::Delete(m) => (
+        m.key.clone(),
+        delete(
+          deps.branch(),
+          env.clone(),
+          info.clone(),
+          config,
+          authn.clone(),
+          m,
+        ),
+      ),
+    };
+    let r = r.map_err(|e| e.context(format!("op {}", i).as_str()))?;
+    // the attributes of each op go to its result, as they would repeat
+    let attr = |name: &str| {
+      r.attributes
+        .iter()
+        .find(|a| a.key == name)
+        .and_then(|a| a.value.parse().ok())
+    };
+    results.push(msg::OpResult {
+      key,
+      revision: attr("revision"),
+      seq: attr("seq"),
+    });
+  }
+  Ok(Response::new().set_data(to_binary(&msg::ExecuteAnswer::Batch(results))?))
+}
+
+/// loads an entry the account may read. missing and expired entries are none,
+/// and so are unreadable ones in privacy mode.
+fn load_readable(
+  deps: Deps,
+  env: &Env,
+  config: &ContractConfig,
+  account: &Addr,
+  owner: &Addr,
+  key: &str,
+) -> Result<Option<Data>, ContractError> {
+  let data = bucket_reader(deps.storage, owner)
+    .may_load(key.as_bytes())?
+    .filter(|d| !d.is_expired(env));
+  if let Some(data) = data {
+    if !is_readable(deps.storage, &data, account)? {
+      if config.privacy_mode {
+        return Ok(None);
+      }
+      return Err(ContractError::Unauthorized("not a reader".into()));
+    }
+    return Ok(Some(data));
+  }
+  Ok(None)
 }
 
 pub fn get(
   deps: Deps,
-  _env: Env,
-  authn: Option<Addr>,
+  env: Env,
+  config: &ContractConfig,
+  authn: Option<Authn>,
   msg: msg::QueryGet,
-) -> StdResult<msg::QueryAnswer> {
+) -> Result<msg::QueryAnswer, ContractError> {
   if authn.is_none() {
-    return Err(StdError::generic_err("unauthorized"));
+    return Err(ContractError::Unauthorized(
+      "no permit or viewing key".into(),
+    ));
   }
-  let bkt = bucket_reader(deps.storage);
-  let key = msg.key.as_bytes();
-  if let Some(data) = bkt.may_load(key)? {
-    if !data.authz.is_readable(authn.unwrap().as_str()) {
-      return Err(StdError::generic_err("unauthorized"));
+  let authn = authn.unwrap();
+  authn.check(Scope::Read, Some(msg.key.as_str()))?;
+  let account = authn.account;
+  let owner = match msg.owner {
+    Some(s) => deps.api.addr_validate(s.as_str())?,
+    None => account.clone(),
+  };
+  let data = load_readable(deps, &env, config, &account, &owner, &msg.key)?;
+  Ok(msg::QueryAnswer::Data(
+    data.map(|d| data_output(msg.key, d)),
+  ))
+}
+
+fn data_output(key: String, data: Data) -> msg::DataOutput {
+  msg::DataOutput {
+    key,
+    version: data.version,
+    data: data.data,
+    authz: data.authz,
+    revision: data.revision,
+    expires_at: data.expires_at,
+    pending_owner: data.pending_owner.map(|a| a.to_string()),
+    created_at: data.created_at,
+    updated_at: data.updated_at,
+    updated_by: data.updated_by,
+  }
+}
+
+/// get of several keys of an owner at once
+pub fn get_many(
+  deps: Deps,
+  env: Env,
+  config: &ContractConfig,
+  authn: Option<Authn>,
+  msg: msg::QueryGetMany,
+) -> Result<msg::QueryAnswer, ContractError> {
+  if authn.is_none() {
+    return Err(ContractError::Unauthorized(
+      "no permit or viewing key".into(),
+    ));
+  }
+  let authn = authn.unwrap();
+  authn.check(Scope::Read, None)?;
+  if msg.keys.len() > defs::LIST_MAX_LIMIT as usize {
+    return Err(ContractError::QuotaExceeded(format!(
+      "more than {} keys",
+      defs::LIST_MAX_LIMIT
+    )));
+  }
+  let account = authn.account.clone();
+  let owner = match msg.owner {
+    Some(s) => deps.api.addr_validate(s.as_str())?,
+    None => account.clone(),
+  };
+  let mut outs = Vec::new();
+  for key in msg.keys {
+    let data = if authn.allows_key(key.as_str()) {
+      load_readable(deps, &env, config, &account, &owner, &key)
+    } else if config.privacy_mode {
+      Ok(None)
+    } else {
+      Err(ContractError::Unauthorized(
+        "key not allowed by permit".into(),
+      ))
+    };
+    outs.push(match data {
+      Ok(Some(data)) => msg::GetManyResult::Found(Box::new(data_output(key, data))),
+      Ok(None) => msg::GetManyResult::Missing { key },
+      Err(ContractError::Unauthorized(_)) => msg::GetManyResult::Forbidden { key },
+      Err(e) => return Err(e),
+    });
+  }
+  Ok(msg::QueryAnswer::Many(outs))
+}
+
+/// like get, without the data
+pub fn get_meta(
+  deps: Deps,
+  env: Env,
+  config: &ContractConfig,
+  authn: Option<Authn>,
+  msg: msg::QueryGetMeta,
+) -> Result<msg::QueryAnswer, ContractError> {
+  if authn.is_none() {
+    return Err(ContractError::Unauthorized(
+      "no permit or viewing key".into(),
+    ));
+  }
+  let authn = authn.unwrap();
+  authn.check(Scope::Read, Some(msg.key.as_str()))?;
+  let account = authn.account;
+  let owner = match msg.owner {
+    Some(s) => deps.api.addr_validate(s.as_str())?,
+    None => account.clone(),
+  };
+  let data = load_readable(deps, &env, config, &account, &owner, &msg.key)?;
+  Ok(msg::QueryAnswer::Meta(data.map(|data| msg::MetaOutput {
+    key: msg.key,
+    owner: data.authz.owner().to_string(),
+    version: data.version,
+    size: data.data.len() as u64,
+    revision: data.revision,
+    expires_at: data.expires_at,
+    pending_owner: data.pending_owner.map(|a| a.to_string()),
+    created_at: data.created_at,
+    updated_at: data.updated_at,
+    updated_by: data.updated_by,
+  })))
+}
+
+pub fn exists(
+  deps: Deps,
+  env: Env,
+  config: &ContractConfig,
+  authn: Option<Authn>,
+  msg: msg::QueryExists,
+) -> Result<msg::QueryAnswer, ContractError> {
+  if authn.is_none() {
+    return Err(ContractError::Unauthorized(
+      "no permit or viewing key".into(),
+    ));
+  }
+  let authn = authn.unwrap();
+  authn.check(Scope::Read, Some(msg.key.as_str()))?;
+  let account = authn.account;
+  let owner = match msg.owner {
+    Some(s) => deps.api.addr_validate(s.as_str())?,
+    None => account.clone(),
+  };
+  let data = load_readable(deps, &env, config, &account, &owner, &msg.key)?;
+  Ok(msg::QueryAnswer::Exists(data.is_some()))
+}
+
+/// keys of the owner readable by the account, looking at no more than
+/// LIST_MAX_SCAN keys.
+pub fn list(
+  deps: Deps,
+  env: Env,
+  _config: &ContractConfig,
+  authn: Option<Authn>,
+  msg: msg::QueryList,
+) -> Result<msg::QueryAnswer, ContractError> {
+  if authn.is_none() {
+    return Err(ContractError::Unauthorized(
+      "no permit or viewing key".into(),
+    ));
+  }
+  let authn = authn.unwrap();
+  authn.check(Scope::Read, None)?;
+  let account = authn.account.clone();
+  let limit = msg
+    .limit
+    .unwrap_or(defs::LIST_DEFAULT_LIMIT)
+    .min(defs::LIST_MAX_LIMIT) as usize;
+  let with_version = msg.with_version.unwrap_or(false);
+
+  let owner = match msg.owner {
+    Some(s) => deps.api.addr_validate(s.as_str())?,
+    None => account.clone(),
+  };
+
+  let bkt = bucket_reader(deps.storage, &owner);
+  let mut outs = Vec::new();
+  let mut keys = index::keys_after(
+    deps.storage,
+    &[defs::INDEX_BUCKET_KEY, owner.as_bytes()],
+    msg.start_after.as_deref(),
+  );
+  let mut scanned = 0;
+  let mut last_key = None;
+  while outs.len() < limit && scanned < defs::LIST_MAX_SCAN {
+    let key = match keys.next() {
+      Some(key) => key?,
+      None => break,
+    };
+    scanned += 1;
+    last_key = Some(key.clone());
+    if !authn.allows_key(key.as_str()) {
+      continue;
+    }
+    if let Some(data) = bkt.may_load(key.as_bytes())? {
+      if data.is_expired(&env) || !is_readable(deps.storage, &data, &account)? {
+        continue;
+      }
+      outs.push(msg::ListOutput {
+        key,
+        version: if with_version {
+          Some(data.version)
+        } else {
+          None
+        },
+      });
+    }
+  }
+  // stopped before the end of the index, by the limit or the scan bound
+  let next = if outs.len() >= limit || scanned >= defs::LIST_MAX_SCAN {
+    last_key
+  } else {
+    None
+  };
+  Ok(msg::QueryAnswer::List(msg::ListPageOutput {
+    entries: outs,
+    next,
+  }))
+}
+
+/// moves the given entries of the global legacy bucket into their owner's namespace.
+/// the legacy bucket has no index, so the keys must be known to the caller.
+/// an entry whose key is already used by its owner is left in the legacy bucket,
+/// and so is one whose owner is not a valid address, counted as failed.
+pub fn migrate_legacy(
+  deps: DepsMut,
+  _env: Env,
+  _info: MessageInfo,
+  msg: msg::ExecuteMigrateLegacy,
+) -> Result<Response, ContractError> {
+  if msg.keys.len() > defs::LIST_MAX_LIMIT as usize {
+    return Err(ContractError::QuotaExceeded(format!(
+      "more than {} keys",
+      defs::LIST_MAX_LIMIT
+    )));
+  }
+
+  let mut migrated = 0;
+  let mut skipped = 0;
+  let mut failed = 0;
+  for key in msg.keys {
+    let data = legacy_bucket_writer(deps.storage).may_load(key.as_bytes())?;
+    if let Some(data) = data {
+      // UpdateAuthz once accepted any owner string
+      let owner = match deps.api.addr_validate(data.authz.owner()) {
+        Ok(owner) => owner,
+        Err(_) => {
+          failed += 1;
+          continue;
+        }
+      };
+      let mut bkt = bucket_writer(deps.storage, &owner);
+      if bkt.may_load(key.as_bytes())?.is_some() {
+        skipped += 1;
+        continue;
+      }
+      bkt.save(key.as_bytes(), &data)?;
+      index::insert(
+        deps.storage,
+        &[defs::INDEX_BUCKET_KEY, owner.as_bytes()],
+        key.as_str(),
+      )?;
+      changes::record(deps.storage, &owner, key.as_str(), false)?;
+      legacy_bucket_writer(deps.storage).remove(key.as_bytes());
+      migrated += 1;
     }
-    let out = msg::DataOutput {
+  }
+  Ok(
+    Response::new()
+      .add_attribute("migrated", migrated.to_string())
+      .add_attribute("skipped", skipped.to_string())
+      .add_attribute("failed", failed.to_string()),
+  )
+}
+
+pub fn get_revision(
+  deps: Deps,
+  env: Env,
+  config: &ContractConfig,
+  authn: Option<Authn>,
+  msg: msg::QueryGetRevision,
+) -> Result<msg::QueryAnswer, ContractError> {
+  if authn.is_none() {
+    return Err(ContractError::Unauthorized(
+      "no permit or viewing key".into(),
+    ));
+  }
+  let authn = authn.unwrap();
+  authn.check(Scope::Read, Some(msg.key.as_str()))?;
+  let account = authn.account;
+  let owner = match msg.owner {
+    Some(s) => deps.api.addr_validate(s.as_str())?,
+    None => account.clone(),
+  };
+  if load_readable(deps, &env, config, &account, &owner, &msg.key)?.is_none() {
+    return Ok(msg::QueryAnswer::Revision(None));
+  }
+  let rev = history::load(
+    deps.storage,
+    &owner,
+    &msg.key,
+    config.history_depth,
+    msg.revision,
+  )?;
+  Ok(msg::QueryAnswer::Revision(rev.map(|r| {
+    msg::RevisionOutput {
       key: msg.key,
-      version: data.version,
-      data: data.data,
-      authz: data.authz,
+      revision: r.revision,
+      version: r.version,
+      data: Some(r.data),
+      time: r.time,
+      writer: r.writer.to_string(),
+    }
+  })))
+}
+
+pub fn list_revisions(
+  deps: Deps,
+  env: Env,
+  config: &ContractConfig,
+  authn: Option<Authn>,
+  msg: msg::QueryListRevisions,
+) -> Result<msg::QueryAnswer, ContractError> {
+  if authn.is_none() {
+    return Err(ContractError::Unauthorized(
+      "no permit or viewing key".into(),
+    ));
+  }
+  let authn = authn.unwrap();
+  authn.check(Scope::Read, Some(msg.key.as_str()))?;
+  let account = authn.account;
+  let owner = match msg.owner {
+    Some(s) => deps.api.addr_validate(s.as_str())?,
+    None => account.clone(),
+  };
+  let mut outs = Vec::new();
+  if load_readable(deps, &env, config, &account, &owner, &msg.key)?.is_some() {
+    let revs = history::list(deps.storage, &owner, &msg.key, config.history_depth)?;
+    for r in revs {
+      outs.push(msg::RevisionOutput {
+        key: msg.key.clone(),
+        revision: r.revision,
+        version: r.version,
+        data: None,
+        time: r.time,
+        writer: r.writer.to_string(),
+      });
+    }
+  }
+  Ok(msg::QueryAnswer::Revisions(outs))
+}
+
+/// changes after `seq` to entries the account can read now, see
+/// is_change_readable, looking at no more than CHANGES_MAX_SCAN log entries.
+pub fn changes_since(
+  deps: Deps,
+  env: Env,
+  _config: &ContractConfig,
+  authn: Option<Authn>,
+  msg: msg::QueryChangesSince,
+) -> Result<msg::QueryAnswer, ContractError> {
+  if authn.is_none() {
+    return Err(ContractError::Unauthorized(
+      "no permit or viewing key".into(),
+    ));
+  }
+  let authn = authn.unwrap();
+  authn.check(Scope::Read, None)?;
+  let account = authn.account.clone();
+  let limit = msg
+    .limit
+    .unwrap_or(defs::LIST_DEFAULT_LIMIT)
+    .min(defs::LIST_MAX_LIMIT) as usize;
+  let latest = changes::latest(deps.storage)?;
+  if msg.seq.saturating_add(1) < changes::oldest(deps.storage)? {
+    return Err(ContractError::NotFound(format!(
+      "changes after {}",
+      msg.seq
+    )));
+  }
+  let end = latest.min(msg.seq.saturating_add(defs::CHANGES_MAX_SCAN));
+
+  let mut outs = Vec::new();
+  let mut last_seq = msg.seq.min(latest);
+  for seq in msg.seq.saturating_add(1)..=end {
+    if outs.len() >= limit {
+      break;
+    }
+    last_seq = seq;
+    let change = match changes::load(deps.storage, seq)? {
+      Some(c) => c,
+      None => continue,
     };
-    Ok(msg::QueryAnswer::Data(Some(out)))
-  } else {
-    Ok(msg::QueryAnswer::Data(None))
+    if !authn.allows_key(change.key.as_str())
+      || !is_change_readable(deps.storage, &env, &change, &account)?
+    {
+      continue;
+    }
+    outs.push(msg::ChangeOutput {
+      seq,
+      owner: change.owner.to_string(),
+      key: change.key,
+      deleted: change.deleted,
+    });
   }
+  Ok(msg::QueryAnswer::Changes(msg::ChangesOutput {
+    changes: outs,
+    last_seq,
+  }))
 }
@@ -0,0 +1,102 @@
+use crate::contract_config::ContractConfig;
+use crate::error::ContractError;
+use crate::{defs, msg};
+use cosmwasm_std::{
+  to_binary, Addr, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult, Storage,
+};
+use cosmwasm_storage::{bucket, bucket_read, singleton, singleton_read};
+use secret_toolkit::crypto::{sha_256, Prng};
+use subtle::ConstantTimeEq;
+
+// SNIP-20 style viewing keys. only the sha256 hash of a key is stored.
+
+pub const VIEWING_KEY_PREFIX: &str = "api_key_";
+
+fn save_hash(storage: &mut dyn Storage, account: &Addr, key: &str) -> StdResult<()> {
+  bucket::<[u8; 32]>(storage, defs::VIEWING_KEY_BUCKET_KEY)
+    .save(account.as_bytes(), &sha_256(key.as_bytes()))
+}
+
+/// compares in constant time, even if the account has no key
+pub fn check(storage: &dyn Storage, account: &Addr, key: &str) -> Result<bool, ContractError> {
+  let stored =
+    bucket_read::<[u8; 32]>(storage, defs::VIEWING_KEY_BUCKET_KEY).may_load(account.as_bytes())?;
+  let given = sha_256(key.as_bytes());
+  match stored {
+    Some(h) => Ok(bool::from(h.ct_eq(&given))),
+    None => {
+      let _ = [0u8; 32].ct_eq(&given);
+      Ok(false)
+    }
+  }
+}
+
+pub fn authenticate(deps: Deps, vk: &msg::ViewingKey) -> Result<Addr, ContractError> {
+  let account = deps.api.addr_validate(vk.address.as_str())?;
+  if !check(deps.storage, &account, vk.key.as_str())? {
+    return Err(ContractError::Unauthorized("wrong viewing key".into()));
+  }
+  Ok(account)
+}
+
+/// seeds the generator, so that keys cannot be guessed from public inputs only
+pub fn init_seed(
+  storage: &mut dyn Storage,
+  env: &Env,
+  info: &MessageInfo,
+  entropy: &str,
+) -> StdResult<()> {
+  let seed = sha_256(
+    &[
+      entropy.as_bytes(),
+      env.block.height.to_be_bytes().as_slice(),
+      env.block.time.nanos().to_be_bytes().as_slice(),
+      env.contract.address.as_bytes(),
+      info.sender.as_bytes(),
+    ]
+    .concat(),
+  );
+  singleton::<Vec<u8>>(storage, defs::VIEWING_KEY_SEED_KEY).save(&seed.to_vec())
+}
+
+pub fn create(
+  deps: DepsMut,
+  env: Env,
+  info: MessageInfo,
+  _config: &ContractConfig,
+  msg: msg::ExecuteCreateViewingKey,
+) -> Result<Response, ContractError> {
+  let seed = singleton_read::<Vec<u8>>(deps.storage, defs::VIEWING_KEY_SEED_KEY)
+    .may_load()?
+    .unwrap_or_default();
+  let entropy = [
+    env.block.height.to_be_bytes().as_slice(),
+    env.block.time.nanos().to_be_bytes().as_slice(),
+    info.sender.as_bytes(),
+    msg.entropy.as_bytes(),
+  ]
+  .concat();
+  let mut rng = Prng::new(&seed, &entropy);
+  let rand = rng.rand_bytes();
+  singleton::<Vec<u8>>(deps.storage, defs::VIEWING_KEY_SEED_KEY)
+    .save(&rng.rand_bytes().to_vec())?;
+
+  let key = format!(
+    "{}{}",
+    VIEWING_KEY_PREFIX,
+    Binary::from(&rand[..]).to_base64()
+  );
+  save_hash(deps.storage, &info.sender, key.as_str())?;
+  Ok(Response::new().set_data(to_binary(&msg::ExecuteAnswer::ViewingKey(key))?))
+}
+
+pub fn set(
+  deps: DepsMut,
+  _env: Env,
+  info: MessageInfo,
+  _config: &ContractConfig,
+  msg: msg::ExecuteSetViewingKey,
+) -> Result<Response, ContractError> {
+  save_hash(deps.storage, &info.sender, msg.key.as_str())?;
+  Ok(Response::new())
+}
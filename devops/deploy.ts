@@ -1,4 +1,5 @@
 import fs from "fs";
+import crypto from "crypto";
 import * as lib from './lib.js';
 
 const deploy_storage = async (wc: lib.WalletClient, config: lib.Config): Promise<lib.ContractInfo> => {
@@ -7,7 +8,7 @@ const deploy_storage = async (wc: lib.WalletClient, config: lib.Config): Promise
     wc.client,
     "../contract/storage/contract.wasm.gz",
     //"../storage-contract/contract.wasm.gz",
-    {},
+    { entropy: crypto.randomBytes(32).toString("base64") },
     config.gas.storeStorage,
     config.gas.instantiateStorage,
   );
@@ -31,7 +32,7 @@ const deploy_app = async (wc: lib.WalletClient, config: lib.Config, storageInfo:
 
 const setup = async (wc: lib.WalletClient, config: lib.Config, storageInfo: lib.ContractInfo, appInfo: lib.ContractInfo) => {
   const msg = {
-    set_applications: { applications: [appInfo.address] },
+    add_application: { address: appInfo.address, code_hash: appInfo.hash, version: config.name },
   };
   await lib.exec(wc, storageInfo, msg, config.gas.setApplications);
 }
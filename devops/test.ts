@@ -14,10 +14,11 @@ const setData = async (wc: lib.WalletClient, info: lib.ContractInfo, key: string
   } }, gas);
 }
 
-const getData = async (wc: lib.WalletClient, info: lib.ContractInfo, key: string): Promise<number|null> => {
+const getData = async (wc: lib.WalletClient, info: lib.ContractInfo, owner: string, key: string): Promise<number|null> => {
   const permit = await lib.createPermit(wc, info.address);
   const r = await lib.query(wc, info, { get: {
     key,
+    owner,
     permit,
   } }) as App.QueryAnswer;
   //console.log('getData=', r);
@@ -36,8 +37,9 @@ const main = async (argv: string[]) => {
 
   console.log(info);
   const key = "test";
+  const owner = wc[0].wallet.address;
 
-  if (await getData(dummy[0], info.appInfo, `${key}-1`) == null) {
+  if (await getData(dummy[0], info.appInfo, owner, `${key}-1`) == null) {
     await setData(wc[0], info.appInfo, `${key}-1`, 31, "p_u_b_l_i_c");
     await setData(wc[0], info.appInfo, `${key}-2`, 52, "p_r_i_v_a_t_e");
     await setData(wc[0], info.appInfo, `${key}-3`, 73, { p_r_o_t_e_c_t_e_d: dummy[0].wallet.address });
@@ -48,16 +50,16 @@ const main = async (argv: string[]) => {
     name: 'Error',
   });
 
-  assert.equal(await getData(dummy[0], info.appInfo, `${key}-1`), '31');
-  assert.rejects(getData(dummy[0], info.appInfo, `${key}-2`), {
+  assert.equal(await getData(dummy[0], info.appInfo, owner, `${key}-1`), '31');
+  assert.rejects(getData(dummy[0], info.appInfo, owner, `${key}-2`), {
     name: 'Error',
-    message: 'Generic error: unauthorized'
+    message: '[unauthorized] not a reader'
   });
-  assert.equal(await getData(dummy[0], info.appInfo, `${key}-3`), '73');
-  assert.rejects(getData(dummy[1], info.appInfo, `${key}-3`), {
+  assert.equal(await getData(dummy[0], info.appInfo, owner, `${key}-3`), '73');
+  assert.rejects(getData(dummy[1], info.appInfo, owner, `${key}-3`), {
     // dummy[1] is not authz for key-3
     name: 'Error',
-    message: 'Generic error: unauthorized'
+    message: '[unauthorized] not a reader'
   });
 };
 